@@ -0,0 +1,39 @@
+//! Time guessing every word in a dictionary one at a time, and then in parallel.
+//!
+//! Usage: cargo run --release --example check_all_timing -- [dictionary] [max words]
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::time::Instant;
+use wordle_solve::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| "answers.txt".to_owned());
+    let max = args.next().map(|n| n.parse()).transpose()?.unwrap_or(2000);
+
+    let (words, _) = read_dictionary(BufReader::new(File::open(path)?), None)?;
+    let knowledge = Knowledge::new(5);
+    let dictionary = words.into_iter()
+        .filter(|word| knowledge.check_word(word, false))
+        .take(max)
+        .collect::<BTreeSet<_>>();
+    let letter_freq = letter_frequencies(&dictionary);
+
+    let start = Instant::now();
+    let serial = dictionary.iter()
+        .map(|word| guess_word(word, dictionary.clone(), None, &letter_freq, &Strategy::Frequency,
+            false))
+        .collect::<Vec<_>>();
+    let serial_time = start.elapsed();
+
+    let start = Instant::now();
+    let parallel = check_all_words_parallel(&dictionary, &letter_freq);
+    let parallel_time = start.elapsed();
+
+    assert!(serial.iter().zip(&parallel).all(|(guesses, result)| *guesses == result.guesses));
+    println!("{} words: {:.2?} serially, {:.2?} in parallel ({:.1}x)", dictionary.len(),
+        serial_time, parallel_time, serial_time.as_secs_f64() / parallel_time.as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Drive the solver in memory, with the feedback computed from a known answer instead of typed in.
+//!
+//! Usage: cargo run --example embed -- <answer> [dictionary]
+
+use std::fs::File;
+use std::io::BufReader;
+use wordle_solve::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let answer = args.next().ok_or("usage: embed <answer> [dictionary]")?;
+    let path = args.next().unwrap_or_else(|| "answers.txt".to_owned());
+
+    let mut knowledge = Knowledge::new(answer.chars().count());
+    let (words, _) = read_dictionary(BufReader::new(File::open(path)?), None)?;
+    let mut candidates = words.into_iter()
+        .filter(|word| knowledge.check_word(word, false))
+        .collect::<Vec<_>>();
+    let letter_freq = letter_frequencies(&candidates);
+
+    for turn in 1 .. {
+        let Some(guess) = best_candidates(candidates.iter().cloned(), &knowledge, &letter_freq)
+            .into_iter()
+            .next()
+        else {
+            return Err(format!("{:?} isn't in the dictionary", answer).into());
+        };
+        let feedback = check_guess(&answer, &guess);
+        println!("{}: {} ({} candidates)", turn, format_infos(&feedback), candidates.len());
+        if guess == answer {
+            break;
+        }
+        knowledge.add_infos(&feedback, false)?;
+        candidates.retain(|word| knowledge.check_word(word, false));
+    }
+    Ok(())
+}
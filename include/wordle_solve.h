@@ -0,0 +1,45 @@
+/* C interface to the wordle-solve library. Build with
+ * `cargo rustc --release --lib --crate-type cdylib --features ffi` and link against the resulting
+ * libwordle_solve shared library.
+ *
+ * Ownership: a solver from wordle_solver_new must be freed with wordle_solver_free, and a string
+ * from wordle_solver_suggestions must be freed with wordle_string_free. Strings passed in are only
+ * borrowed for the duration of the call. */
+
+#ifndef WORDLE_SOLVE_H
+#define WORDLE_SOLVE_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct FfiSolver FfiSolver;
+
+/* Create a solver from newline-separated dictionary words, for words of the given length.
+ * Returns NULL if the dictionary isn't valid UTF-8. */
+FfiSolver *wordle_solver_new(const char *words, size_t num_letters);
+
+/* Apply a row of feedback, with each letter prefixed by '*' for green, '?' for yellow, or '!'
+ * for gray (e.g. "*c?r!a!n!e"). Returns 0 on success, or -1 if the row couldn't be parsed or
+ * contradicts earlier feedback. */
+int wordle_solver_apply(FfiSolver *solver, const char *row);
+
+/* How many candidates are left. */
+size_t wordle_solver_candidate_count(const FfiSolver *solver);
+
+/* Up to max suggested guesses, best first, separated by newlines. Free with wordle_string_free. */
+char *wordle_solver_suggestions(const FfiSolver *solver, size_t max);
+
+/* Free a string from wordle_solver_suggestions. Does nothing if it's NULL. */
+void wordle_string_free(char *s);
+
+/* Free a solver. Does nothing if it's NULL. */
+void wordle_solver_free(FfiSolver *solver);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,69 @@
+//! Reading feedback from the JSON responses of online Wordle clones' APIs.
+//!
+//! The expected shape is an array with an object for each tile, in order:
+//!
+//! ```json
+//! [{"letter": "c", "state": "correct"}, {"letter": "r", "state": "present"}, ...]
+//! ```
+//!
+//! where `state` is one of `correct`, `present` or `absent` (or `green`, `yellow` or `gray`).
+
+use crate::*;
+use serde::Deserialize;
+
+/// How feedback is entered in interactive mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Letters prefixed with `*`, `?` or `!`, as taken by [`parse_input`].
+    Text,
+
+    /// A game API's JSON response on each line, as taken by [`parse_api_response`].
+    Api,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(InputFormat::Text),
+            "api" => Ok(InputFormat::Api),
+            other => Err(format!("unknown input format {:?}", other)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiTile {
+    letter: char,
+    state: ApiState,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ApiState {
+    #[serde(alias = "green")]
+    Correct,
+    #[serde(alias = "yellow")]
+    Present,
+    #[serde(alias = "gray", alias = "grey")]
+    Absent,
+}
+
+/// Parse a game API's JSON response for one guess into feedback for each letter.
+pub fn parse_api_response(json: &str, num_letters: usize) -> Result<Vec<Info>, SolveError> {
+    let tiles: Vec<ApiTile> = serde_json::from_str(json)
+        .map_err(|e| SolveError::BadApiResponse(e.to_string()))?;
+    if tiles.len() != num_letters {
+        return Err(SolveError::LengthMismatch { expected: num_letters, actual: tiles.len() });
+    }
+    Ok(tiles.into_iter()
+        .map(|tile| {
+            let c = tile.letter.to_ascii_lowercase();
+            match tile.state {
+                ApiState::Correct => Info::Exact(c),
+                ApiState::Present => Info::Somewhere(c),
+                ApiState::Absent => Info::No(c),
+            }
+        })
+        .collect())
+}
@@ -1,7 +1,11 @@
 use std::collections::hash_map::*;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use rand::{RngExt, SeedableRng};
+use rand::rngs::StdRng;
 use structopt::StructOpt;
 use wordle_solve::*;
 
@@ -11,10 +15,42 @@ struct Args {
     #[structopt(default_value = "5")]
     num_letters: usize,
 
-    /// Path to a dictionary file, with one word per line.
+    /// Path to a dictionary file, with one word per line, or a CSV file of `word,count` lines.
     #[structopt(default_value = "/usr/share/dict/words")]
     dictionary_path: String,
 
+    /// Download the dictionary from this URL instead of reading a file (needs the "net" feature,
+    /// except for `file://` URLs). A copy is kept in ~/.cache/wordle-solve (or $XDG_CACHE_HOME),
+    /// and used instead if it can't be downloaded next time.
+    #[structopt(long)]
+    dictionary_url: Option<String>,
+
+    /// Format of the dictionary file ("plain" or "csv"). If not given, a CSV file is detected by
+    /// its "word,count" header line.
+    #[structopt(long, possible_values = &["plain", "csv"])]
+    dict_format: Option<DictFormat>,
+
+    /// Path to a separate list of possible answers, one word per line. Then only these are
+    /// candidates, but guesses can be any word from the dictionary (which should include them).
+    #[structopt(long)]
+    answers_path: Option<String>,
+
+    /// How to enter feedback: "text" (like `*c?r!a!n!e`), or "api" for a JSON array of
+    /// `{"letter": "c", "state": "correct"}` objects on each line, as returned by some online
+    /// Wordle clones. States are "correct", "present" or "absent".
+    #[structopt(long, default_value = "text", possible_values = &["text", "api"])]
+    input_format: InputFormat,
+
+    /// Enter feedback as the guess on one line, then the row of squares from the game's share text
+    /// (like `🟩🟨⬛🟨⬛`) on the next.
+    #[structopt(long)]
+    emoji_mode: bool,
+
+    /// Accept rows of feedback with fewer letters than the word, leaving the rest of the word
+    /// unconstrained. Otherwise a short row is an error, since it's usually a typo.
+    #[structopt(long)]
+    partial_rows: bool,
+
     /// Enable debug output?
     #[structopt(short = "v", long)]
     verbose: bool,
@@ -23,6 +59,87 @@ struct Args {
     #[structopt(long)]
     word: Option<String>,
 
+    /// How to print the guesses made with --word or --check-all-words: "text", "json" for an array
+    /// of objects like `{"word":"robot","num_guesses":4,"dict_size":2315,"guesses":[{"guess":
+    /// "arose","remaining":57},...],"solved":true,"failed":false}`, or "csv". With json or csv,
+    /// anything else that would be printed alongside goes to stderr instead.
+    #[structopt(long, default_value = "text", possible_values = &["text", "json", "csv"])]
+    output_format: OutputFormat,
+
+    /// Shorthand for `--output-format json`.
+    #[structopt(long)]
+    json: bool,
+
+    /// With --word, write each guess made, its feedback and the number of candidates left to this
+    /// file, one per line (like `crane !c?r!a!n!e 42`). The file can be picked up again with
+    /// --resume.
+    #[structopt(long)]
+    guesses_file: Option<String>,
+
+    /// Apply the feedback from a file written by --guesses-file before starting interactive mode.
+    #[structopt(long)]
+    resume: Option<String>,
+
+    /// Known green letters, as a pattern like `c_a_e` (a letter where it's known, `_` where it
+    /// isn't), applied before starting interactive mode.
+    #[structopt(long)]
+    pattern: Option<String>,
+
+    /// Always play these words as the first guesses, in order, whatever feedback they get (like
+    /// `crane,solid`), and let the solver take over after them. Used with --word and
+    /// --check-all-words.
+    #[structopt(long, use_delimiter = true)]
+    fixed_openers: Vec<String>,
+
+    /// Always start with this word: it's the top suggestion until feedback has been entered, and
+    /// the first guess with --word and --check-all-words. It doesn't have to be in the dictionary.
+    #[structopt(long, conflicts_with = "fixed-openers")]
+    first_guess: Option<String>,
+
+    /// With --word, play in "normal mode": guess whichever dictionary word best narrows down the
+    /// candidates, even if it can't be the answer itself.
+    #[structopt(long)]
+    normal_mode: bool,
+
+    /// A letter known to be in the word but not at the given positions (counting from one), like
+    /// `e:1,4`, applied before starting interactive mode. Can be given more than once.
+    #[structopt(long, number_of_values = 1, parse(try_from_str = parse_present_not_at))]
+    present_not_at: Vec<(char, Vec<usize>)>,
+
+    /// Play a game: a secret word is picked from the dictionary, and you try to guess it.
+    #[structopt(long)]
+    play: bool,
+
+    /// Seed for picking the secret word in --play mode, or the words to try with --sample. The
+    /// same seed and dictionary always pick the same words. If not given, a random seed is used
+    /// (and printed, so the run can be repeated).
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Print the answer for this day's Wordle (counting from 0 on 19 June 2021), taken from the
+    /// answers list (or the dictionary) in the order it's given. That's only right if the list is
+    /// in the game's original order. Needs --spoil as well, so it isn't shown by accident.
+    #[structopt(long)]
+    day: Option<u32>,
+
+    /// Really show the answer for --day.
+    #[structopt(long)]
+    spoil: bool,
+
+    /// In --play mode, don't show how the solver would have found the word after a loss.
+    #[structopt(long)]
+    no_reveal: bool,
+
+    /// In --play mode, once one word fitting the guesses so far is at least this likely to be the
+    /// answer (between 0 and 1, using the dictionary's word frequency weights), guess it
+    /// automatically instead of asking.
+    #[structopt(long)]
+    auto_when_confident: Option<f64>,
+
+    /// In --play and --word modes, print the game's share text (the emoji grid) at the end.
+    #[structopt(long)]
+    share: bool,
+
     /// Try to guess every word in the dictionary.
     ///
     /// For each word prints one line of the following format:
@@ -30,12 +147,275 @@ struct Args {
     /// <guesses required> <the word> (<size of dictionary>) [<guessed word> (<words remaining>)]...
     #[structopt(long)]
     check_all_words: bool,
+
+    /// With --check-all-words, only try a random sample of this many words (picked using --seed).
+    #[structopt(long)]
+    sample: Option<usize>,
+
+    /// With --check-all-words, only try every this-many-th word in dictionary order, starting
+    /// with the first. Like --sample, but always the same words without needing a seed.
+    #[structopt(long, conflicts_with = "sample")]
+    every_nth: Option<usize>,
+
+    /// Give up on a word after this many guesses with --word or --check-all-words, and count it as
+    /// failed. The failed words are listed after the summary. This is also the number of guesses
+    /// the interactive solver plans for (6 if not given).
+    #[structopt(long)]
+    max_guesses: Option<usize>,
+
+    /// With --check-all-words, don't print a line for each word, only the summary at the end.
+    #[structopt(long)]
+    summary_only: bool,
+
+    /// With --check-all-words, draw a bar chart of how many words took each number of guesses,
+    /// scaled to the terminal width (from $COLUMNS, or 80 columns).
+    #[structopt(long)]
+    histogram: bool,
+
+    /// With --check-all-words, write how many guesses each word took to this file, one `word
+    /// count` per line (`X` for a failure), for comparing against later with --compare-snapshot.
+    #[structopt(long)]
+    snapshot: Option<String>,
+
+    /// With --check-all-words, compare how many guesses each word took against a file written by
+    /// --snapshot, and list the words which changed.
+    #[structopt(long)]
+    compare_snapshot: Option<String>,
+
+    /// Try to guess each word in this file of known past answers (one per line), and report how
+    /// often the solver got them within six guesses, and how many guesses it took on average.
+    #[structopt(long)]
+    target_list: Option<String>,
+
+    /// Play against a host like Absurdle, which instead of picking an answer, gives whichever
+    /// feedback leaves the most candidates. Shows how many guesses the solver needs in the worst
+    /// case.
+    #[structopt(long)]
+    absurdle: bool,
+
+    /// Solve this many boards at once with the same guesses, like Dordle (2) or Quordle (4). Each
+    /// turn, a guess is suggested, and then the feedback is asked for from each board which isn't
+    /// solved yet.
+    #[structopt(long)]
+    boards: Option<usize>,
+
+    /// Try to guess every word in the dictionary (or the answers), checking that the word is never
+    /// wrongly ruled out along the way. Reports any words where it was, and exits with an error if
+    /// so. Takes the openers, --normal-mode and --max-guesses into account.
+    #[structopt(long)]
+    self_check: bool,
+
+    /// Show which of the ten most common letters in the dictionary are tried by these openers
+    /// (like `crane,solid`), and which are missed.
+    #[structopt(long, use_delimiter = true)]
+    coverage: Vec<String>,
+
+    /// Show which first letters make words hardest to narrow down: for words starting with each
+    /// letter, how many candidates are left on average after the solver's first guess.
+    #[structopt(long)]
+    first_letter_stats: bool,
+
+    /// Write the tree of guesses the solver makes for every word, starting with the given opener,
+    /// to a Graphviz DOT file.
+    #[structopt(long, number_of_values = 2, value_names = &["OPENER", "FILE"])]
+    tree_dot: Option<Vec<String>>,
+
+    /// Work out the most guesses the solver would ever need for any word in the dictionary,
+    /// starting with this opener.
+    #[structopt(long)]
+    worst_case: Option<String>,
+
+    /// Whether to color the letters of each guess by their feedback, and the letters of
+    /// suggestions already known to be in the right place: "always", "never", or "auto" to only
+    /// do it when output is going to a terminal and the NO_COLOR environment variable isn't set.
+    #[structopt(long, default_value = "auto", possible_values = &["auto", "always", "never"])]
+    color: ColorMode,
+
+    /// Shorthand for `--color never`.
+    #[structopt(long)]
+    no_color: bool,
+
+    /// Show a keyboard with letters colored by what is known about them, each turn.
+    #[structopt(long, alias = "letters-remaining")]
+    keyboard: bool,
+
+    /// Show the known letters each turn as a pattern, like `_ o _ _ t (also: r)`. Type "pattern"
+    /// at the prompt to show it once.
+    #[structopt(long)]
+    letters_only: bool,
+
+    /// Whether a gray tile means the letter is absent from the whole word. Set to false for
+    /// clones where a gray tile only rules the letter out of that one position.
+    #[structopt(long, parse(try_from_str), default_value = "true")]
+    gray_means_absent: bool,
+
+    /// Never suggest a guess that would get the same feedback whatever the answer is, when there
+    /// are others that wouldn't.
+    #[structopt(long)]
+    prefer_new_info: bool,
+
+    /// Only suggest guesses from the words in this file (one per line), which don't have to be in
+    /// the dictionary. They're still ranked against the candidates from the dictionary.
+    #[structopt(long)]
+    guess_from: Option<String>,
+
+    /// Only suggest guesses with at least this many different letters, as long as there are any.
+    #[structopt(long)]
+    min_unique_letters: Option<usize>,
+
+    /// Among suggestions which are equally good, put the ones which are easiest to type on a
+    /// QWERTY keyboard first: those which switch hands between letters the most.
+    #[structopt(long)]
+    keyboard_ergonomic: bool,
+
+    /// Before applying feedback, check that at least one candidate fits it, and if none do, warn
+    /// and ask for it again (entering the same feedback twice applies it anyway). Leave this off
+    /// for games whose answers might not be in the dictionary.
+    #[structopt(long)]
+    interactive_guess_check: bool,
+
+    /// Say why this word isn't a candidate any more (or that it still is), each turn. Type
+    /// "why WORD" at the prompt to ask about a word once.
+    #[structopt(long)]
+    explain_no: Option<String>,
+
+    /// Show how likely each candidate is to be the answer, using the dictionary's word frequency
+    /// weights (or treating them all as equally likely, if it has none).
+    #[structopt(long)]
+    probabilities: bool,
+
+    /// Show how many more guesses the solver expects to need if you play the top suggestion. This
+    /// plays out every candidate, so it's only shown once there are few enough of them.
+    #[structopt(long)]
+    expected_guesses: bool,
+
+    /// Print only one line per turn: the number of candidates and the top few suggestions, with
+    /// no prompts. Useful for status bars.
+    #[structopt(long)]
+    compact: bool,
+
+    /// Don't print prompts or headings in interactive mode: each turn, just print the number of
+    /// candidates on one line, and then the top suggestions one per line. Useful when piping input
+    /// in.
+    #[structopt(long)]
+    no_prompt: bool,
+
+    /// Write the letter frequencies computed from the dictionary to this file: as JSON if its name
+    /// ends in `.json`, or otherwise as tab-separated `letter<TAB>frequency` lines.
+    #[structopt(long)]
+    dump_frequencies: Option<String>,
+
+    /// Don't use letter frequency for ranking suggestions at all: just rank by the number of unique
+    /// letters, and then alphabetically.
+    #[structopt(long)]
+    no_frequency: bool,
+
+    /// With the frequency strategy, how much to count it against a suggestion when it reuses
+    /// letters from the previous guess that weren't found in the word, relative to the letter's
+    /// frequency. 0 means no penalty.
+    #[structopt(long, default_value = "0")]
+    recent_letter_penalty: f64,
+
+    /// With the frequency strategy, how to value letters: "global" by how common they are anywhere
+    /// in the words, or "position" by how common they are in the position they're in. "position"
+    /// only applies to the interactive solver, with the frequency or adaptive strategy.
+    #[structopt(long, default_value = "global", possible_values = &["global", "position"])]
+    scoring_mode: ScoringMode,
+
+    /// Shorthand for `--scoring-mode position`.
+    #[structopt(long)]
+    positional: bool,
+
+    /// With the frequency strategy, rank words by how many of their letters (counting repeats)
+    /// aren't known about yet, instead of by how many unique letters they have. Can help with long
+    /// words, where repeated letters still narrow down positions.
+    #[structopt(long)]
+    count_repeats: bool,
+
+    /// How to rank guesses: "frequency", "entropy", "lookahead", "positional", "discriminating",
+    /// or "adaptive" (which switches between the others depending on how many candidates are
+    /// left).
+    #[structopt(long, default_value = "frequency", possible_values = &["frequency", "entropy",
+        "lookahead", "positional", "discriminating", "adaptive"])]
+    strategy: Strategy,
+
+    /// When the candidates left are all (nearly) anagrams of each other, switch to the positional
+    /// strategy, which picks the guess expected to get the most greens. The adaptive strategy
+    /// always does this.
+    #[structopt(long)]
+    prefer_positional_greens: bool,
+
+    /// Also show the top suggestion from each of these strategies every turn, for comparison,
+    /// e.g. `--show-strategies freq,entropy`. Each one adds the cost of its ranking.
+    #[structopt(long, use_delimiter = true)]
+    show_strategies: Vec<Strategy>,
+
+    /// With the adaptive strategy, switch from frequency to entropy below this many candidates.
+    #[structopt(long, default_value = "1000")]
+    adaptive_entropy_below: usize,
+
+    /// With the adaptive strategy, switch to lookahead below this many candidates.
+    #[structopt(long, default_value = "50")]
+    adaptive_lookahead_below: usize,
+}
+
+/// The flag for the mode the arguments ask for, if it's one which doesn't run the interactive
+/// solver.
+fn batch_mode(args: &Args) -> Option<&'static str> {
+    let modes = [
+        (args.play, "--play"),
+        (args.day.is_some(), "--day"),
+        (args.word.is_some(), "--word"),
+        (args.absurdle, "--absurdle"),
+        (args.target_list.is_some(), "--target-list"),
+        (args.check_all_words, "--check-all-words"),
+        (args.tree_dot.is_some(), "--tree-dot"),
+        (args.worst_case.is_some(), "--worst-case"),
+        (!args.coverage.is_empty(), "--coverage"),
+        (args.first_letter_stats, "--first-letter-stats"),
+        (args.self_check, "--self-check"),
+        (args.boards.is_some(), "--boards"),
+    ];
+    modes.iter().find(|(set, _)| *set).map(|&(_, flag)| flag)
 }
 
 fn main() -> io::Result<()> {
-    let args = Args::from_args();
+    let mut args = Args::from_args();
+    if let Strategy::Adaptive { entropy_below, lookahead_below } = &mut args.strategy {
+        *entropy_below = args.adaptive_entropy_below;
+        *lookahead_below = args.adaptive_lookahead_below;
+    }
+
+    if args.positional || args.scoring_mode == ScoringMode::Position {
+        if let Some(mode) = batch_mode(&args) {
+            println!("--scoring-mode position only applies to the interactive solver, not {}", mode);
+            std::process::exit(1);
+        }
+        if !matches!(args.strategy, Strategy::Frequency | Strategy::Adaptive { .. }) {
+            println!("--scoring-mode position only applies to the frequency and adaptive strategies");
+            std::process::exit(1);
+        }
+        if args.recent_letter_penalty != 0. || args.count_repeats {
+            println!("--scoring-mode position can't be combined with --recent-letter-penalty or \
+                --count-repeats");
+            std::process::exit(1);
+        }
+    }
+
+    let mut knowledge = Knowledge::new(args.num_letters)
+        .with_gray_means_absent(args.gray_means_absent);
+    // What's known before any guesses, for filtering other lists of words read in.
+    let base_knowledge = knowledge.clone();
 
-    let mut knowledge = Knowledge::new(args.num_letters);
+    if let Some(url) = &args.dictionary_url {
+        match fetch_dictionary(url, &cache_dir(), download) {
+            Ok(path) => args.dictionary_path = path.to_string_lossy().into_owned(),
+            Err(e) => {
+                println!("unable to download the dictionary from {}: {}", url, e);
+                std::process::exit(1);
+            }
+        }
+    }
 
     let words_file = match File::open(&args.dictionary_path) {
         Ok(f) => f,
@@ -48,28 +428,13 @@ fn main() -> io::Result<()> {
         }
     };
 
-    // Build a list of all words of the correct length. Use a BTreeSet because we want the words to
-    // be in order (makes it easier to debug things when order is deterministic).
-    let mut dictionary = BTreeSet::<String>::new();
-    for res in BufReader::new(words_file).lines() {
-        let word = res?;
-        if knowledge.check_word(&word, false) {
-            dictionary.insert(word);
-        }
-    }
-
-    // Build a map of letters to how often they occur in N-letter words.
-    let mut letter_freq = HashMap::<char, f64>::new();
-    for word in &dictionary {
-        for c in word.chars() {
-            *letter_freq.entry(c).or_insert(0.) += 1.;
-        }
-    }
+    let (full_dictionary, mut word_weights, answers) =
+        load_words(BufReader::new(words_file), &args)?;
+    let (mut dictionary, mut letter_freq) = full_dictionary.into_parts();
+    let mut position_freq = build_position_freq(&dictionary);
 
-    // Normalize by total number of letters.
-    let total_letters = letter_freq.values().sum::<f64>();
-    for v in letter_freq.values_mut() {
-        *v /= total_letters;
+    if args.verbose && !word_weights.is_empty() {
+        eprintln!("loaded frequency weights for {} words", word_weights.len());
     }
 
     if args.verbose {
@@ -81,14 +446,91 @@ fn main() -> io::Result<()> {
         }
     }
 
+    if let Some(path) = &args.dump_frequencies {
+        let json = path.ends_with(".json");
+        write_frequencies(&letter_freq, json, &mut File::create(path)?)?;
+    }
+
+    if let Some(day) = args.day {
+        if !args.spoil {
+            println!("this would show the answer for Wordle {}; add --spoil to see it", day);
+            std::process::exit(1);
+        }
+        let path = args.answers_path.as_ref().unwrap_or(&args.dictionary_path);
+        let (words, _) = read_dictionary(BufReader::new(File::open(path)?), None)?;
+        let words = words.into_iter()
+            .filter(|word| base_knowledge.check_word(word, false))
+            .collect::<Vec<_>>();
+        match answer_for_day(&words, day) {
+            Some(answer) => println!("the answer for Wordle {} is {}", day, answer),
+            None if words.is_empty() => println!("no answers in {:?}", path),
+            None => println!("{:?} only has the answers up to Wordle {}", path, words.len() - 1),
+        }
+        return Ok(());
+    }
+
+    if args.play {
+        let seed = args.seed.unwrap_or_else(rand::random);
+        let Some(secret) = pick_secret(answers.as_ref().unwrap_or(&dictionary), seed) else {
+            println!("no words in dictionary!");
+            std::process::exit(1);
+        };
+        println!("Playing with seed {}.", seed);
+        let auto = args.auto_when_confident.map(|p| (p, &word_weights));
+        let rows = play(&dictionary, secret, use_color(args.color, args.no_color), auto,
+            &mut io::stdin().lock(), &mut io::stdout())?;
+        if args.share {
+            println!("\n{}", render_share(None, &rows, PLAY_MAX_GUESSES));
+        }
+        if !is_won(&rows) && !args.no_reveal {
+            reveal_solution(secret, &dictionary, &letter_freq, &args.strategy, &mut io::stdout())?;
+        }
+        return Ok(());
+    }
+
+    let openers = match &args.first_guess {
+        Some(word) => vec![word.clone()],
+        None => args.fixed_openers.clone(),
+    };
+    if let Some(opener) = openers.iter().find(|w| w.chars().count() != args.num_letters) {
+        println!("wrong number of letters in opener \"{}\"", opener);
+        std::process::exit(1);
+    }
+    for opener in openers.iter().filter(|w| !dictionary.contains(*w)) {
+        eprintln!("warning: opener \"{}\" isn't in the dictionary", opener);
+    }
+
+    let output_format = if args.json { OutputFormat::Json } else { args.output_format };
+    let scoring_mode = if args.positional { ScoringMode::Position } else { args.scoring_mode };
+
     if let Some(word) = args.word {
         if word.len() != args.num_letters {
             println!("wrong number of letters in \"{}\"", word);
             std::process::exit(1);
         }
-        println!("{} words in dictionary", dictionary.len());
-        println!("checking: {}", word);
-        let guesses = guess_word(&word, dictionary, &letter_freq);
+        let (candidates, guess_pool) = match &answers {
+            Some(answers) => (answers.clone(), Some(&dictionary)),
+            None => (dictionary.clone(), None),
+        };
+        if output_format == OutputFormat::Text {
+            println!("{} words in dictionary", dictionary.len());
+            println!("checking: {}", word);
+        }
+        let guesses = guess_word_with_openers(&word, &openers, candidates, guess_pool,
+            &letter_freq, &args.strategy, args.normal_mode, args.max_guesses);
+        if output_format != OutputFormat::Text {
+            let result = WordResult {
+                num_guesses: guesses.len(),
+                dict_size: dictionary.len(),
+                guesses: guesses.clone(),
+                word: word.clone(),
+            };
+            write_results(&[result], output_format, &mut io::stdout())?;
+            if let Some(path) = &args.guesses_file {
+                write_transcript(&word, &guesses, &mut File::create(path)?)?;
+            }
+            return Ok(());
+        }
         for (guess_num, (guess, remaining)) in guesses.iter().enumerate() {
             if guess.is_empty() {
                 println!("dunno lol");
@@ -99,262 +541,3542 @@ fn main() -> io::Result<()> {
             println!("    {} candidates left", remaining);
         }
         println!("{} guesses required", guesses.len());
+        if args.share {
+            let rows = guesses.iter()
+                .take_while(|(guess, _)| !guess.is_empty())
+                .map(|(guess, _)| check_guess(&word, guess))
+                .collect::<Vec<_>>();
+            println!("\n{}", render_share(None, &rows, PLAY_MAX_GUESSES));
+        }
+        if let Some(path) = &args.guesses_file {
+            write_transcript(&word, &guesses, &mut File::create(path)?)?;
+        }
         return Ok(());
     }
 
-    if args.check_all_words {
-        check_all_words(&dictionary, &letter_freq);
+    if args.absurdle {
+        let candidates = answers.as_ref().unwrap_or(&dictionary).clone();
+        println!("{} candidates", candidates.len());
+        let guesses = guess_adversarial(candidates, &openers, &letter_freq, &args.strategy,
+            args.max_guesses);
+        // The word the host ends up with got the same feedback as all the others did.
+        let answer = guesses.last().map(|(word, _)| word.as_str()).filter(|w| !w.is_empty());
+        for (guess_num, (guess, remaining)) in guesses.iter().enumerate() {
+            if guess.is_empty() {
+                println!("gave up");
+                break;
+            }
+            println!("  {}: guessing {}", guess_num, guess);
+            match answer {
+                Some(answer) => println!("    {} ({} candidates left)",
+                    format_infos(&check_guess(answer, guess)), remaining),
+                None => println!("    {} candidates left", remaining),
+            }
+        }
+        println!("{} guesses required", guesses.len());
         return Ok(());
     }
 
-    loop {
-        if dictionary.is_empty() {
-            println!("no candidates left!");
-            return Ok(());
+    if let Some(path) = &args.target_list {
+        let (targets, _) = read_dictionary(BufReader::new(File::open(path)?), None)?;
+        let targets = targets.into_iter()
+            .filter(|word| base_knowledge.check_word(word, false))
+            .collect::<Vec<_>>();
+        let results = check_all_words(&dictionary, answers.as_ref(), targets.iter(),
+            &openers, &letter_freq, &args.strategy, args.max_guesses, Some(output_format),
+            &mut io::stdout().lock())?;
+        let report = accuracy_report(&results, args.max_guesses.unwrap_or(PLAY_MAX_GUESSES));
+        if output_format == OutputFormat::Text {
+            println!("{}", report);
+        } else {
+            eprintln!("{}", report);
         }
+        return Ok(());
+    }
 
-        println!("{} candidates.", dictionary.len());
-        let best = best_candidates(dictionary.iter(), &knowledge, &letter_freq);
-        print_words("By most unique letters and letter frequency",
-            best.iter().map(|w| format!("\n\t{}", w)));
-
-        loop {
-            print!("Type the guess you made. Prefix each letter with: green=*, yellow=?, gray=!: ");
-            io::stdout().flush()?;
-            let mut inp = String::new();
-            io::stdin().read_line(&mut inp)?;
-            inp = inp.trim().to_owned();
-            if inp.is_empty() {
-                return Ok(());
+    if args.check_all_words {
+        let targets = answers.as_ref().unwrap_or(&dictionary);
+        let words = if let Some(sample) = args.sample {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            eprintln!("sampling {} words with seed {}", sample, seed);
+            sample_words(targets, sample, seed)
+        } else if let Some(stride) = args.every_nth {
+            if stride == 0 {
+                println!("--every-nth must be at least 1");
+                std::process::exit(1);
             }
-            match parse_input(&inp, args.num_letters) {
-                Err(e) => {
-                    println!("Input error: {}", e);
-                    continue;
-                }
-                Ok(infos) => {
-                    if let Err(e) = knowledge.add_infos(&infos, args.verbose) {
-                        println!("Bad input: {}", e);
-                        continue;
-                    }
-                }
+            eprintln!("trying 1 in every {} words", stride);
+            every_nth(targets, stride)
+        } else {
+            targets.iter().collect()
+        };
+        let print_each = (!args.summary_only).then_some(output_format);
+        if output_format == OutputFormat::Text {
+            if !openers.is_empty() {
+                println!("forced opening: {}", openers.join(", "));
             }
-            break;
+        } else if !openers.is_empty() {
+            eprintln!("forced opening: {}", openers.join(", "));
         }
-
-        dictionary.retain(|word| knowledge.check_word(word, args.verbose));
+        let mut stdout = io::stdout().lock();
+        let results = check_all_words(&dictionary, answers.as_ref(), words.iter().copied(),
+            &openers, &letter_freq, &args.strategy, args.max_guesses, print_each, &mut stdout)?;
+        let snapshot = snapshot_counts(&words, &results);
+        if let Some(path) = &args.snapshot {
+            write_snapshot(&snapshot, &mut File::create(path)?)?;
+        }
+        let old = match &args.compare_snapshot {
+            Some(path) => Some((path.as_str(), read_snapshot(BufReader::new(File::open(path)?))?)),
+            None => None,
+        };
+        let report = CheckReport {
+            max_guesses: args.max_guesses,
+            histogram_width: args.histogram.then(terminal_width),
+            compare_to: old.as_ref().map(|(path, old)| (*path, old)),
+        };
+        report.write(&words, &results, output_format, &mut stdout, &mut io::stderr())?;
+        return Ok(());
     }
-}
 
-fn check_all_words(dictionary: &BTreeSet<String>, letter_freq: &HashMap<char, f64>) {
-    for word in dictionary {
-        let guesses = guess_word(word, dictionary.clone(), letter_freq);
-        print!("{} {} ({})", guesses.len(), word, dictionary.len());
-        for (guess, remaining) in guesses {
-            print!(" {} ({})", guess, remaining);
-        }
-        println!();
+    if let Some(tree_dot) = &args.tree_dot {
+        let (opener, path) = (&tree_dot[0], &tree_dot[1]);
+        let words = dictionary.iter().collect::<Vec<_>>();
+        let tree = build_decision_tree(opener, &words, &knowledge, &letter_freq, &args.strategy);
+        let mut out = io::BufWriter::new(File::create(path)?);
+        write_dot(&tree, &mut out)?;
+        out.flush()?;
+        return Ok(());
     }
-}
 
-fn guess_word(
-    word: &str,
-    mut candidates: BTreeSet<String>,
-    letter_freq: &HashMap<char, f64>,
-) -> Vec<(String, usize)> {
-    let mut guesses = vec![];
-    let mut knowledge = Knowledge::new(word.len());
+    if let Some(opener) = &args.worst_case {
+        let words = dictionary.iter().collect::<Vec<_>>();
+        let worst = worst_case_for_opener(opener, &words, &args.strategy);
+        println!("starting with {}, every word is found within {} guesses", opener, worst);
+        return Ok(());
+    }
 
-    loop {
-        let best_guesses = best_candidates(candidates.iter(), &knowledge, letter_freq);
-        if best_guesses.is_empty() {
-            guesses.push((String::new(), 0));
-            return guesses;
-        }
-        let guess = best_guesses[0].clone();
-        if guess == word {
-            guesses.push((guess, 1));
-            return guesses;
-        }
+    if !args.coverage.is_empty() {
+        let openers = args.coverage.iter().map(String::as_str).collect::<Vec<_>>();
+        println!("{}", opener_coverage(&openers, &letter_freq, 10));
+        return Ok(());
+    }
 
-        let infos = check_guess(word, &guess);
-        if let Err(e) = knowledge.add_infos(&infos, false) {
-            panic!("ERROR on {} (guessing {}): {}", word, guess, e);
+    if args.first_letter_stats {
+        let words = dictionary.iter().collect::<Vec<_>>();
+        let Some(opener) = args.strategy.rank(&words, &knowledge, &letter_freq).into_iter().next()
+        else {
+            println!("no words in dictionary!");
+            std::process::exit(1);
+        };
+        println!("after guessing {}:", opener);
+        for (letter, count, average) in first_letter_stats(&words, &opener) {
+            println!("{}: {:.1} candidates left on average ({} words)", letter, average, count);
         }
-
-        candidates.retain(|word| knowledge.check_word(word, false));
-        guesses.push((guess, candidates.len()));
+        return Ok(());
     }
-}
 
-fn print_words<T: AsRef<str>>(msg: &str, words: impl Iterator<Item=T>) {
-    print!("{}: ", msg);
-    let mut it = words.enumerate().peekable();
-    while let Some((i, word)) = it.next() {
-        print!("{}", word.as_ref());
-        if i == 9 {
-            break;
+    if args.self_check {
+        let (candidates, guess_pool) = match &answers {
+            Some(answers) => (answers, Some(&dictionary)),
+            None => (&dictionary, None),
+        };
+        let mut failures = 0;
+        for word in candidates {
+            if let Err(e) = guess_word_checked(word, &openers, candidates.clone(), guess_pool,
+                &letter_freq, &args.strategy, args.normal_mode, args.max_guesses)
+            {
+                println!("{}: {}", word, e);
+                failures += 1;
+            }
         }
-        if it.peek().is_some() {
-            print!(", ");
+        println!("{} of {} words failed the self-check", failures, candidates.len());
+        if failures > 0 {
+            std::process::exit(1);
         }
+        return Ok(());
     }
-    let cnt = it.count();
-    if cnt > 0 {
-        println!(", and {} more", cnt);
-    } else {
-        println!();
+
+    if let Some(num_boards) = args.boards {
+        let mut boards = MultiBoard::new(num_boards, &knowledge,
+            answers.as_ref().unwrap_or(&dictionary));
+        return play_boards(&mut boards, &dictionary, &letter_freq, args.num_letters,
+            &mut io::stdin().lock(), &mut io::stdout());
     }
-}
 
-fn parse_input(inp: &str, num_letters: usize) -> Result<Vec<Info>, String> {
-    let mut flag = None;
-    let mut infos = vec![];
-    for c in inp.chars() {
-        if infos.len() == num_letters {
-            return Err("too many letters in input".to_owned());
-        }
-        if c.is_whitespace() {
-            continue;
+    // Every word that could be guessed, for the "probe" command.
+    let mut allowed = dictionary.iter().cloned().collect::<Vec<_>>();
+
+    // With a separate list of answers, only they are candidates, and the whole dictionary is for
+    // guessing.
+    let mut guessable = None;
+    if let Some(answers) = answers {
+        guessable = Some(std::mem::replace(&mut dictionary, answers));
+    }
+
+    // How many candidates there were when they were last shown, starting from the whole
+    // dictionary, for showing how many each turn eliminated.
+    let mut previous_count = dictionary.len();
+
+    // How many guesses have had their feedback entered, for --first-guess, and for only
+    // suggesting possible answers for the last guess.
+    let mut guesses_made = 0;
+
+    if let Some(path) = &args.resume {
+        for infos in read_transcript(BufReader::new(File::open(path)?), args.num_letters)? {
+            knowledge.add_infos(&infos, args.verbose)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            guesses_made += 1;
         }
-        if flag.is_none() {
-            flag = Some(c);
-            continue;
+        dictionary.retain(|word| knowledge.check_word(word, args.verbose));
+    }
+
+    if let Some(pattern) = &args.pattern {
+        let result = parse_known_pattern(pattern, args.num_letters).and_then(|known| {
+            known.into_iter().try_for_each(|(idx, c)| Ok(knowledge.require_at(idx, c)?))
+        });
+        if let Err(e) = result {
+            println!("bad --pattern {:?}: {}", pattern, e);
+            std::process::exit(1);
         }
-        let info = match flag.unwrap() {
-            '*' => Info::Exact(c),
-            '?' => Info::Somewhere(c),
-            '!' => Info::No(c),
-            other => {
-                return Err(format!("unknown annotation {:?}", other));
-            }
-        };
-        infos.push(info);
-        flag = None;
     }
-    if let Some(extra) = flag {
-        return Err(format!("unprocessed input {:?}", extra));
+
+    for (c, positions) in &args.present_not_at {
+        knowledge.require_somewhere(*c);
+        if let Err(e) = knowledge.forbid_positions(*c, positions) {
+            println!("bad --present-not-at for {:?}: {}", c, e);
+            std::process::exit(1);
+        }
     }
-    Ok(infos)
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    if args.pattern.is_some() || !args.present_not_at.is_empty() {
+        dictionary.retain(|word| knowledge.check_word(word, args.verbose));
+    }
+
+    let probes = match &args.guess_from {
+        Some(path) => {
+            let (words, _) = read_dictionary(BufReader::new(File::open(path)?), None)?;
+            Some(words.into_iter()
+                .filter(|word| knowledge.check_word(word, false))
+                .collect::<Vec<_>>())
+        }
+        None => None,
+    };
+
+    // The current best guess and candidate count, for printing if the user interrupts us.
+    let status = Arc::new(Mutex::new((None::<String>, dictionary.len())));
+    #[cfg(feature = "ctrlc")]
+    install_interrupt_handler(Arc::clone(&status));
+
+    // In compact and no-prompt modes, only the suggestions go to stdout; anything else goes to
+    // stderr.
+    let quiet = args.compact || args.no_prompt;
+    let report = |msg: String| if quiet { eprintln!("{}", msg) } else { println!("{}", msg) };
+    let color = use_color(args.color, args.no_color);
+
+    // Letters from the last guess entered, for --recent-letter-penalty.
+    let mut previous_letters = vec![];
+
+    // Feedback which didn't fit any candidate, for --interactive-guess-check, so that entering it
+    // again applies it anyway.
+    let mut unconfirmed = None::<String>;
+
+    // What's known from what was actually entered, without the greens inferred from the
+    // candidates, for filtering the words again when the dictionary is reloaded.
+    let mut entered_knowledge = knowledge.clone();
+
+    loop {
+        if dictionary.is_empty() {
+            if args.compact {
+                println!("{}", format_compact(0, &[] as &[&str]));
+            } else {
+                println!("no candidates left!");
+            }
+            return Ok(());
+        }
+
+        let candidates = dictionary.iter().collect::<Vec<_>>();
+        let strategy = if args.prefer_positional_greens && is_positional_endgame(&candidates) {
+            Strategy::Positional
+        } else {
+            args.strategy.for_candidates(&candidates)
+        };
+        let penalty = args.recent_letter_penalty;
+        let tuned = strategy == Strategy::Frequency && (penalty != 0. || args.count_repeats);
+        let by_position = strategy == Strategy::Frequency
+            && scoring_mode == ScoringMode::Position;
+        let final_turn = guesses_made + 1 == args.max_guesses.unwrap_or(PLAY_MAX_GUESSES);
+        let forced_first = args.first_guess.is_some() && guesses_made == 0;
+        // Anagrams always tie in the plain frequency ranking, but anything which reorders it
+        // might split them up.
+        let anagrams_tie = strategy == Strategy::Frequency && !by_position
+            && !args.prefer_new_info && !args.keyboard_ergonomic && !final_turn && !forced_first;
+        // Words from the dictionary which could be guessed, if that's more than the candidates.
+        let guesses = guessable.as_ref().map(|words| {
+            words.iter().filter(|word| knowledge.check_word(word, false)).collect::<Vec<_>>()
+        });
+        let mut best = match &probes {
+            Some(probes) if tuned =>
+                best_candidates_avoiding(probes.iter().cloned(), &knowledge, &letter_freq,
+                    &previous_letters, penalty, args.count_repeats),
+            None if tuned =>
+                best_candidates_avoiding(
+                    guesses.as_deref().unwrap_or(&candidates).iter().copied().cloned(),
+                    &knowledge, &letter_freq, &previous_letters, penalty, args.count_repeats),
+            Some(probes) if by_position =>
+                best_candidates_with_position_freq(probes.iter().cloned(), &knowledge,
+                    &position_freq),
+            None if by_position =>
+                best_candidates_with_position_freq(
+                    guesses.as_deref().unwrap_or(&candidates).iter().copied().cloned(),
+                    &knowledge, &position_freq),
+            Some(probes) => strategy.rank_probes(probes, &candidates, &knowledge, &letter_freq),
+            None => match &guesses {
+                Some(guesses) =>
+                    strategy.rank_guesses(guesses, &candidates, &knowledge, &letter_freq),
+                None => strategy.rank(&candidates, &knowledge, &letter_freq),
+            },
+        };
+        if let Some(min) = args.min_unique_letters {
+            require_unique_letters(&mut best, min);
+        }
+        if args.prefer_new_info {
+            demote_uninformative(&mut best, &candidates);
+        }
+        if args.keyboard_ergonomic {
+            prefer_ergonomic(&mut best, &candidates);
+        }
+        if final_turn {
+            best = best_final_guesses(&candidates, &knowledge, &word_weights, &letter_freq);
+        }
+        if let (Some(first), 0) = (&args.first_guess, guesses_made) {
+            best.retain(|word| word != first);
+            best.insert(0, first.clone());
+        }
+        *status.lock().unwrap() = (best.first().map(|w| w.to_string()), dictionary.len());
+
+        if args.compact {
+            println!("{}", format_compact(dictionary.len(), &best));
+        } else if args.no_prompt {
+            println!("{}", format_quiet(dictionary.len(), &best));
+        } else {
+            println!("{} candidates.", format_count_change(previous_count, dictionary.len()));
+            previous_count = dictionary.len();
+            if args.keyboard {
+                print!("{}", render_keyboard(&knowledge));
+            }
+            if args.letters_only {
+                println!("{}", knowledge.letters_pattern());
+            }
+            if let Some(word) = &args.explain_no {
+                println!("{}", explain_word(&knowledge, word));
+            }
+            let show = |word: &str| if color {
+                highlight_known_greens(word, &knowledge)
+            } else {
+                word.to_owned()
+            };
+            if strategy == Strategy::Frequency {
+                // Only show one of each group of anagrams.
+                let groups = if anagrams_tie {
+                    collapse_anagrams(&best)
+                } else {
+                    collapse_anagrams_ranked(&best)
+                };
+                print_words(strategy.description(), groups
+                    .into_iter()
+                    .map(|(w, n)| format_anagram_group(&show(&w), n)));
+            } else {
+                print_words(strategy.description(),
+                    best.iter().map(|w| format!("\n\t{}", show(w))));
+            }
+            for line in compare_strategies(&args.show_strategies, &candidates, &knowledge,
+                &letter_freq)
+            {
+                println!("{}", line);
+            }
+            match (args.expected_guesses, best.first()) {
+                (true, Some(_)) if candidates.len() > EXPECTED_GUESSES_MAX_CANDIDATES => {
+                    println!("Too many candidates to estimate the guesses needed yet.");
+                }
+                (true, Some(top)) => {
+                    let tree = build_decision_tree(top, &candidates, &knowledge, &letter_freq,
+                        &strategy);
+                    println!("Expected ~{:.1} more guesses if you play {}",
+                        tree.expected_guesses(), top);
+                }
+                _ => (),
+            }
+            if args.probabilities {
+                print_words("Most likely answers",
+                    candidate_probabilities(&candidates, &word_weights)
+                        .into_iter()
+                        .map(|(w, p)| format!("{} {:.1}%", w, p * 100.)));
+            }
+        }
+
+        loop {
+            if args.emoji_mode && !args.compact && !args.no_prompt {
+                print!("Type the guess you made: ");
+            } else if !args.compact && !args.no_prompt {
+                print!("Type the guess you made. Prefix each letter with: green=*, yellow=?, gray=!: ");
+            }
+            io::stdout().flush()?;
+            let mut inp = String::new();
+            io::stdin().read_line(&mut inp)?;
+            inp = inp.trim().to_owned();
+            if inp.is_empty() {
+                return Ok(());
+            }
+            if inp == "pattern" {
+                report(knowledge.letters_pattern());
+                continue;
+            }
+            if let Some(rest) = inp.strip_prefix("not ") {
+                let result = parse_not_command(rest)
+                    .and_then(|(c, positions)| {
+                        knowledge.forbid_positions(c, &positions).map_err(|e| e.to_string())?;
+                        entered_knowledge.forbid_positions(c, &positions)
+                            .expect("positions can be forbidden from what was entered");
+                        Ok(())
+                    });
+                match result {
+                    Ok(()) => break,
+                    Err(e) => {
+                        report(format!("Bad input: {}", e));
+                        continue;
+                    }
+                }
+            }
+            if let Some(word) = inp.strip_prefix("why ") {
+                report(explain_word(&knowledge, word.trim()));
+                continue;
+            }
+            if inp == "probe" {
+                let pool = probes.as_ref().unwrap_or(&allowed);
+                match best_probe(pool, &candidates) {
+                    Some((guess, remaining)) => report(format!(
+                        "Best probe: {} (~{:.1} candidates left on average)", guess, remaining)),
+                    None => report("No probe words".to_owned()),
+                }
+                continue;
+            }
+            if inp == "reload" {
+                let reloaded = File::open(&args.dictionary_path)
+                    .and_then(|f| load_words(BufReader::new(f), &args));
+                match reloaded {
+                    Ok((reloaded, weights, answers)) => {
+                        let (words, freq) = reloaded.into_parts();
+                        letter_freq = freq;
+                        position_freq = build_position_freq(&words);
+                        word_weights = weights;
+                        let before = dictionary.len();
+                        allowed = words.iter().cloned().collect();
+                        // Same as at startup: with a separate list of answers, only they are
+                        // candidates, and the whole dictionary is for guessing.
+                        let pool = match answers {
+                            Some(answers) => {
+                                guessable = Some(words);
+                                answers
+                            }
+                            None => words,
+                        };
+                        (dictionary, knowledge) = reload_candidates(pool, &entered_knowledge);
+                        report(format!("Reloaded dictionary: {} -> {} candidates", before,
+                            dictionary.len()));
+                    }
+                    Err(e) => report(format!("Unable to reload dictionary: {}", e)),
+                }
+                break;
+            }
+            let parsed = if args.emoji_mode {
+                if !args.compact && !args.no_prompt {
+                    print!("Type the squares from the share text: ");
+                }
+                io::stdout().flush()?;
+                let mut tiles = String::new();
+                io::stdin().read_line(&mut tiles)?;
+                let parsed = parse_emoji_input_with_letters(&inp, &tiles, args.num_letters);
+                // Both lines together are what has to be entered twice for
+                // --interactive-guess-check.
+                inp = format!("{} {}", inp, tiles.trim());
+                parsed
+            } else {
+                match args.input_format {
+                    // Colored text pasted from a terminal game.
+                    InputFormat::Text if inp.contains('\x1b') => parse_ansi(&inp, args.num_letters),
+                    InputFormat::Text if args.partial_rows => {
+                        parse_input_partial(&inp, args.num_letters)
+                    }
+                    InputFormat::Text => parse_input(&inp, args.num_letters),
+                    InputFormat::Api => parse_api_response(&inp, args.num_letters),
+                }
+            };
+            match parsed {
+                Err(e) => {
+                    report(format!("Input error: {}", e));
+                    continue;
+                }
+                Ok(infos) => {
+                    if args.interactive_guess_check && unconfirmed.as_ref() != Some(&inp) {
+                        if let Some(warning) = check_feedback(&knowledge, &infos, &dictionary) {
+                            report(warning);
+                            unconfirmed = Some(inp);
+                            continue;
+                        }
+                    }
+                    unconfirmed = None;
+                    if let Err(e) = knowledge.add_infos(&infos, args.verbose) {
+                        report(format!("Bad input: {}", e));
+                        continue;
+                    }
+                    // Feedback which fits everything known fits what was entered alone too.
+                    entered_knowledge.add_infos(&infos, false)
+                        .expect("feedback fits what was entered");
+                    if color && !quiet {
+                        println!("{}", format_row(&infos, true));
+                    }
+                    guesses_made += 1;
+                    previous_letters = infos.iter()
+                        .map(|info| match info {
+                            Info::Exact(c) | Info::Somewhere(c) | Info::No(c) => *c,
+                        })
+                        .collect();
+                }
+            }
+            break;
+        }
+
+        dictionary.retain(|word| knowledge.check_word(word, args.verbose));
+        let candidates = dictionary.iter().collect::<Vec<_>>();
+        for (idx, c) in knowledge.infer_forced(&candidates) {
+            if args.verbose {
+                eprintln!("all candidates have {:?} at position {}", c, idx);
+            }
+        }
+    }
+}
+
+/// The candidates from a reloaded list of words, and what's known about them: what was entered
+/// so far, and any greens all of them share. Greens inferred from the old candidates don't count,
+/// as the new words might not share them.
+fn reload_candidates(mut words: BTreeSet<String>, entered: &Knowledge)
+    -> (BTreeSet<String>, Knowledge)
+{
+    let mut knowledge = entered.clone();
+    words.retain(|word| knowledge.check_word(word, false));
+    knowledge.infer_forced(&words.iter().collect::<Vec<_>>());
+    (words, knowledge)
+}
+
+/// Why a word isn't a candidate, like `crane: has 'c' at position 1, but 'b' is green there`.
+fn explain_word(knowledge: &Knowledge, word: &str) -> String {
+    match knowledge.check_word_explain(word) {
+        Ok(()) => format!("{}: still a candidate", word),
+        Err(reason) => format!("{}: {}", word, reason),
+    }
+}
+
+/// If applying the feedback would leave no candidates, a warning saying so.
+fn check_feedback(knowledge: &Knowledge, infos: &[Info], candidates: &BTreeSet<String>)
+    -> Option<String>
+{
+    let mut knowledge = knowledge.clone();
+    // Errors are reported when the feedback is actually applied.
+    knowledge.add_infos(infos, false).ok()?;
+    if knowledge.filter_count_capped(candidates, 1) != 0 {
+        None
+    } else {
+        Some(format!("That feedback doesn't fit any of the {} candidates left; is there a typo? \
+            Enter it again to use it anyway.", candidates.len()))
+    }
+}
+
+/// Parse the arguments to the interactive "not" command: a letter followed by the positions
+/// (counting from 1) it isn't at, like `e 1 4`. Returns the positions counting from zero.
+fn parse_not_command(args: &str) -> Result<(char, Vec<usize>), String> {
+    let mut words = args.split_whitespace();
+    let letter = match words.next().map(|w| w.chars().collect::<Vec<_>>()).as_deref() {
+        Some(&[c]) => c,
+        _ => return Err("expected a single letter, like \"not e 1 4\"".to_owned()),
+    };
+    let positions = words
+        .map(|w| match w.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n - 1),
+            _ => Err(format!("bad position {:?}", w)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if positions.is_empty() {
+        return Err("expected at least one position".to_owned());
+    }
+    Ok((letter, positions))
+}
+
+/// Parse a --present-not-at argument, like `e:1,4`, into the letter and the positions (counting
+/// from zero) it isn't at. The positions can be left off, as in `e`.
+fn parse_present_not_at(arg: &str) -> Result<(char, Vec<usize>), String> {
+    let (letter, positions) = arg.split_once(':').unwrap_or((arg, ""));
+    let letter = match letter.chars().collect::<Vec<_>>().as_slice() {
+        &[c] => c,
+        _ => return Err("expected a single letter, like \"e:1,4\"".to_owned()),
+    };
+    let positions = positions.split(',')
+        .filter(|p| !p.is_empty())
+        .map(|p| match p.trim().parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n - 1),
+            _ => Err(format!("bad position {:?}", p)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((letter, positions))
+}
+
+/// On the first Ctrl-C, print the best guess so far and exit. On a second one (if printing somehow
+/// gets stuck), exit immediately.
+#[cfg(feature = "ctrlc")]
+fn install_interrupt_handler(status: Arc<Mutex<(Option<String>, usize)>>) {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    let interrupted = AtomicBool::new(false);
+    let result = ctrlc::set_handler(move || {
+        if interrupted.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        if let Ok(status) = status.try_lock() {
+            let (best, num_candidates) = &*status;
+            println!();
+            println!("{}", interrupt_summary(best.as_deref(), *num_candidates));
+        }
+        std::process::exit(130);
+    });
+    if let Err(e) = result {
+        eprintln!("unable to install Ctrl-C handler: {}", e);
+    }
+}
+
+#[cfg_attr(not(feature = "ctrlc"), allow(dead_code))]
+fn interrupt_summary(best: Option<&str>, num_candidates: usize) -> String {
+    match best {
+        Some(word) => format!("Interrupted with {} candidates left. Best guess: {}", num_candidates, word),
+        None => format!("Interrupted with {} candidates left.", num_candidates),
+    }
+}
+
+/// Read the dictionary, keeping only the words which are valid for this puzzle, along with any
+/// frequency weights for the words.
+fn load_dictionary(
+    reader: impl BufRead,
+    format: Option<DictFormat>,
+    num_letters: usize,
+) -> io::Result<(Dictionary, HashMap<String, f64>)> {
+    let (words, weights) = read_dictionary(reader, format)?;
+    Ok((Dictionary::from_words(words, num_letters), weights))
+}
+
+/// The dictionary, its frequency weights, and the separate list of answers if there is one.
+type LoadedWords = (Dictionary, HashMap<String, f64>, Option<BTreeSet<String>>);
+
+/// Read the dictionary from `reader`, and the list of answers if there is one, as at startup. Any
+/// answer can be guessed, so the answers are added to the dictionary too, and the dictionary's
+/// letter frequencies are set as the arguments ask.
+fn load_words(reader: impl BufRead, args: &Args) -> io::Result<LoadedWords> {
+    let (mut dictionary, weights) = load_dictionary(reader, args.dict_format, args.num_letters)?;
+    let answers = match &args.answers_path {
+        Some(path) => {
+            let (answers, _) = Dictionary::load(Path::new(path), args.num_letters)?.into_parts();
+            dictionary.extend(answers.iter().cloned());
+            Some(answers)
+        }
+        None => None,
+    };
+    Ok((dictionary.with_frequency(!args.no_frequency), weights, answers))
+}
+
+/// Where downloaded dictionaries are kept: $XDG_CACHE_HOME/wordle-solve, or
+/// ~/.cache/wordle-solve.
+fn cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_default()
+        .join("wordle-solve")
+}
+
+/// Get a dictionary with `fetch`, and save it in `cache_dir` under a name made from the URL,
+/// returning the path to the saved copy. If it can't be fetched, or what's fetched doesn't look
+/// like a word list, a copy saved before is used instead, if there is one.
+fn fetch_dictionary(
+    url: &str,
+    cache_dir: &Path,
+    fetch: impl FnOnce(&str) -> io::Result<String>,
+) -> io::Result<PathBuf> {
+    let name = url.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect::<String>();
+    let cached = cache_dir.join(name);
+    let fetched = fetch(url).and_then(|text| {
+        if text.contains('\0') || text.lines().all(|line| line.trim().is_empty()) {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "not a word list"))
+        } else {
+            Ok(text)
+        }
+    });
+    match fetched {
+        Ok(text) => {
+            std::fs::create_dir_all(cache_dir)?;
+            std::fs::write(&cached, text)?;
+            Ok(cached)
+        }
+        Err(e) if cached.exists() => {
+            eprintln!("unable to download {} ({}), so using the copy from last time", url, e);
+            Ok(cached)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Download a file over HTTP(S), or read a `file://` URL.
+fn download(url: &str) -> io::Result<String> {
+    match url.strip_prefix("file://") {
+        Some(path) => std::fs::read_to_string(path),
+        None => download_http(url),
+    }
+}
+
+#[cfg(feature = "net")]
+fn download_http(url: &str) -> io::Result<String> {
+    ureq::get(url).call().map_err(io::Error::other)?.into_string()
+}
+
+#[cfg(not(feature = "net"))]
+fn download_http(_url: &str) -> io::Result<String> {
+    Err(io::Error::other("built without the \"net\" feature, so only file:// URLs work"))
+}
+
+/// Write letter frequencies in alphabetical order, as a JSON object or as TSV lines.
+fn write_frequencies(letter_freq: &HashMap<char, f64>, json: bool, out: &mut impl Write)
+    -> io::Result<()>
+{
+    let sorted = letter_freq.iter().collect::<BTreeMap<_, _>>();
+    if json {
+        serde_json::to_writer_pretty(&mut *out, &sorted)?;
+        writeln!(out)
+    } else {
+        for (letter, freq) in sorted {
+            writeln!(out, "{}\t{}", letter, freq)?;
+        }
+        Ok(())
+    }
+}
+
+/// Try to guess each of the given words, writing the guesses made for each to `out`, and returning
+/// them. If there's a separate list of answers, only those are candidates, but any word in the
+/// dictionary can be guessed. Each game starts with the fixed openers, if there are any. The
+/// results for each word are only written if `print_each` gives a format for them. The words are
+/// solved in parallel, but written in order once they're all done.
+#[allow(clippy::too_many_arguments)]
+fn check_all_words<'a>(
+    dictionary: &BTreeSet<String>,
+    answers: Option<&BTreeSet<String>>,
+    words: impl Iterator<Item=&'a String>,
+    openers: &[String],
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+    max_guesses: Option<usize>,
+    print_each: Option<OutputFormat>,
+    out: &mut impl Write,
+) -> io::Result<Vec<Vec<(String, usize)>>> {
+    let candidates = answers.unwrap_or(dictionary);
+    let guess_pool = answers.map(|_| dictionary);
+    let words = words.collect::<Vec<_>>();
+    let results = solve_words_parallel(&words, candidates, guess_pool, openers, letter_freq,
+        strategy, max_guesses);
+    if let Some(format) = print_each {
+        write_results(&results, format, out)?;
+    }
+    Ok(results.into_iter().map(|result| result.guesses).collect())
+}
+
+/// For the words starting with each letter, how many there are and how many candidates are left
+/// on average after guessing the opener. Sorted with the largest average first.
+fn first_letter_stats<W: AsRef<str>>(words: &[W], opener: &str) -> Vec<(char, usize, f64)> {
+    let distribution = pattern_distribution(opener, words);
+    let mut by_letter = BTreeMap::<char, (usize, usize)>::new();
+    for word in words {
+        let Some(first) = word.as_ref().chars().next() else { continue };
+        let remaining = distribution[&check_guess(word.as_ref(), opener)];
+        let (count, total) = by_letter.entry(first).or_default();
+        *count += 1;
+        *total += remaining;
+    }
+    let mut stats = by_letter.into_iter()
+        .map(|(letter, (count, total))| (letter, count, total as f64 / count as f64))
+        .collect::<Vec<_>>();
+    stats.sort_by(|(_, _, a), (_, _, b)| b.partial_cmp(a).unwrap());
+    stats
+}
+
+/// Pick `count` different words from the dictionary at random (deterministically from the seed),
+/// in dictionary order. If there aren't that many, all of them are picked.
+fn sample_words(dictionary: &BTreeSet<String>, count: usize, seed: u64) -> Vec<&String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut indices = rand::seq::index::sample(&mut rng, dictionary.len(),
+        count.min(dictionary.len())).into_vec();
+    indices.sort_unstable();
+    let mut indices = indices.into_iter().peekable();
+    dictionary.iter()
+        .enumerate()
+        .filter(|(i, _)| indices.next_if_eq(i).is_some())
+        .map(|(_, word)| word)
+        .collect()
+}
+
+/// Pick every `stride`-th word from the dictionary, starting with the first.
+fn every_nth(dictionary: &BTreeSet<String>, stride: usize) -> Vec<&String> {
+    dictionary.iter().step_by(stride).collect()
+}
+
+/// What --check-all-words reports after the results for each word.
+struct CheckReport<'a> {
+    /// Report which words weren't solved within this many guesses.
+    max_guesses: Option<usize>,
+    /// Draw a histogram of the guess counts, this many columns wide.
+    histogram_width: Option<usize>,
+    /// Report how the guess counts differ from a snapshot (read from the named file).
+    compare_to: Option<(&'a str, &'a Snapshot)>,
+}
+
+impl CheckReport<'_> {
+    /// Write the report on the results of `check_all_words`. It goes to `out` along with the
+    /// results when they're text, but to `log` otherwise, so that `out` can still be parsed.
+    fn write(
+        &self,
+        words: &[&String],
+        results: &[Vec<(String, usize)>],
+        format: OutputFormat,
+        out: &mut impl Write,
+        log: &mut impl Write,
+    ) -> io::Result<()> {
+        let report: &mut dyn Write = if format == OutputFormat::Text { out } else { log };
+        writeln!(report, "{}", Summary::new(results))?;
+        if let Some(max) = self.max_guesses {
+            writeln!(report, "{}", failure_report(words, results, max))?;
+        }
+        if let Some(width) = self.histogram_width {
+            write!(report, "{}", render_histogram(results, width))?;
+        }
+        if let Some((path, old)) = self.compare_to {
+            let snapshot = snapshot_counts(words, results);
+            let unmatched = snapshot.keys().filter(|word| !old.contains_key(*word)).count();
+            if unmatched != 0 {
+                writeln!(report, "{} words weren't in {}", unmatched, path)?;
+            }
+            let changes = compare_snapshots(old, &snapshot);
+            if changes.is_empty() {
+                writeln!(report, "no differences from {}", path)?;
+            }
+            for (word, before, after) in &changes {
+                let show = |count: &Option<usize>| count.map_or("X".to_owned(), |n| n.to_string());
+                writeln!(report, "changed: {} {} -> {}", word, show(before), show(after))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Aggregate statistics over the results of `check_all_words`. All but `failures` are over the
+/// words which were solved.
+#[derive(Debug, PartialEq)]
+struct Summary {
+    words: usize,
+    mean: f64,
+    median: f64,
+    std_dev: f64,
+    max: usize,
+    /// How many words took each number of guesses.
+    counts: BTreeMap<usize, usize>,
+    failures: usize,
+}
+
+impl Summary {
+    fn new(results: &[Vec<(String, usize)>]) -> Self {
+        let mut solved = results.iter()
+            .filter(|guesses| guesses.last().is_some_and(|(guess, _)| !guess.is_empty()))
+            .map(|guesses| guesses.len())
+            .collect::<Vec<_>>();
+        solved.sort_unstable();
+        let n = solved.len().max(1) as f64;
+        let mean = solved.iter().sum::<usize>() as f64 / n;
+        let median = match solved.len() {
+            0 => 0.,
+            len if len % 2 == 0 => (solved[len / 2 - 1] + solved[len / 2]) as f64 / 2.,
+            len => solved[len / 2] as f64,
+        };
+        let variance = solved.iter().map(|&count| (count as f64 - mean).powi(2)).sum::<f64>() / n;
+        let mut counts = BTreeMap::new();
+        for &count in &solved {
+            *counts.entry(count).or_default() += 1;
+        }
+        Self {
+            words: results.len(),
+            mean,
+            median,
+            std_dev: variance.sqrt(),
+            max: solved.last().copied().unwrap_or(0),
+            counts,
+            failures: results.len() - solved.len(),
+        }
+    }
+}
+
+/// A summary of how the solver did on real past answers: how many it solved within the given
+/// number of guesses, and the average number of guesses over the ones it solved at all.
+fn accuracy_report(results: &[Vec<(String, usize)>], max_guesses: usize) -> String {
+    let summary = Summary::new(results);
+    let within = results.iter()
+        .filter(|guesses| guesses.len() <= max_guesses
+            && guesses.last().is_some_and(|(guess, _)| !guess.is_empty()))
+        .count();
+    format!("historical accuracy: {}/{} solved within {} guesses ({:.1}%), {:.3} guesses on average",
+        within, summary.words, max_guesses, within as f64 * 100. / summary.words.max(1) as f64,
+        summary.mean)
+}
+
+/// How many of the words were solved within the maximum number of guesses, and which ones failed.
+fn failure_report(words: &[&String], results: &[Vec<(String, usize)>], max_guesses: usize)
+    -> String
+{
+    let failed = words.iter()
+        .zip(results)
+        .filter(|(_, guesses)| guesses.len() > max_guesses
+            || guesses.last().is_none_or(|(guess, _)| guess.is_empty()))
+        .map(|(word, _)| word.as_str())
+        .collect::<Vec<_>>();
+    let mut report = format!("{} words solved in \u{2264}{} guesses; {} words failed",
+        results.len() - failed.len(), max_guesses, failed.len());
+    if !failed.is_empty() {
+        report += &format!("\nfailed: {}", failed.join(", "));
+    }
+    report
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} words: {:.3} guesses on average (median {}, std dev {:.3}), {} at most, {} \
+            failed", self.words, self.mean, self.median, self.std_dev, self.max, self.failures)?;
+        let counts = self.counts.iter()
+            .map(|(guesses, words)| format!("{} ({})", guesses, words))
+            .collect::<Vec<_>>();
+        write!(f, "guesses (words): {}", counts.join(", "))
+    }
+}
+
+/// Draw a bar chart of how many words took each number of guesses, with a row for failures at the
+/// end (if there were any), fitting in the given number of columns. Bar lengths are proportional
+/// to the counts, with the longest filling the available width.
+fn render_histogram(results: &[Vec<(String, usize)>], width: usize) -> String {
+    let mut counts = BTreeMap::<usize, usize>::new();
+    let mut failures = 0;
+    for guesses in results {
+        match guesses.last() {
+            Some((guess, _)) if !guess.is_empty() => *counts.entry(guesses.len()).or_default() += 1,
+            _ => failures += 1,
+        }
+    }
+    let mut rows = match counts.last_key_value() {
+        Some((&most, _)) => (1 ..= most)
+            .map(|n| (n.to_string(), counts.get(&n).copied().unwrap_or(0)))
+            .collect::<Vec<_>>(),
+        None => vec![],
+    };
+    if failures != 0 {
+        rows.push(("X".to_owned(), failures));
+    }
+
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let max_count = rows.iter().map(|&(_, count)| count).max().unwrap_or(0);
+    let count_width = max_count.to_string().len();
+    let bar_width = width.saturating_sub(label_width + count_width + 4).max(1);
+
+    let mut out = String::new();
+    for (label, count) in rows {
+        let bar = (count * bar_width + max_count / 2) / max_count;
+        out += &format!("{:>label_width$} | {} {}\n", label, "#".repeat(bar), count);
+    }
+    out
+}
+
+/// The width of the terminal, going by $COLUMNS, or 80 columns if it isn't set.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS").ok()
+        .and_then(|cols| cols.parse().ok())
+        .unwrap_or(80)
+}
+
+/// Write the guesses made for a word, one per line: the guess, its feedback, and how many
+/// candidates were left afterwards. A failure to find the word isn't written.
+fn write_transcript(word: &str, guesses: &[(String, usize)], out: &mut impl Write)
+    -> io::Result<()>
+{
+    for (guess, remaining) in guesses {
+        if guess.is_empty() {
+            break;
+        }
+        writeln!(out, "{} {} {}", guess, format_infos(&check_guess(word, guess)), remaining)?;
+    }
+    Ok(())
+}
+
+/// Read the feedback for each guess back from a file written by `write_transcript`.
+fn read_transcript(reader: impl BufRead, num_letters: usize) -> io::Result<Vec<Vec<Info>>> {
+    let mut transcript = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let bad_line = |msg: String| io::Error::new(io::ErrorKind::InvalidData,
+            format!("bad transcript line {:?}: {}", line, msg));
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let &[guess, infos, remaining] = fields.as_slice() else {
+            return Err(bad_line("expected a guess, its feedback, and a count".to_owned()));
+        };
+        let infos = parse_input(infos, num_letters).map_err(|e| bad_line(e.to_string()))?;
+        let letters = infos.iter()
+            .map(|info| match info {
+                Info::Exact(c) | Info::Somewhere(c) | Info::No(c) => *c,
+            })
+            .collect::<String>();
+        if letters != guess {
+            return Err(bad_line("feedback doesn't match the guess".to_owned()));
+        }
+        remaining.parse::<usize>().map_err(|e| bad_line(e.to_string()))?;
+        transcript.push(infos);
+    }
+    Ok(transcript)
+}
+
+/// How many guesses each word took in a --check-all-words run, or `None` if it wasn't found.
+type Snapshot = BTreeMap<String, Option<usize>>;
+
+/// Pair up the words tried by `check_all_words` with how many guesses each took.
+fn snapshot_counts(words: &[&String], results: &[Vec<(String, usize)>]) -> Snapshot {
+    words.iter()
+        .zip(results)
+        .map(|(word, guesses)| {
+            let found = guesses.last().is_some_and(|(guess, _)| !guess.is_empty());
+            ((*word).clone(), found.then_some(guesses.len()))
+        })
+        .collect()
+}
+
+/// Write a snapshot of guess counts, one `word count` per line, with `X` for a failure.
+fn write_snapshot(snapshot: &Snapshot, out: &mut impl Write) -> io::Result<()> {
+    for (word, count) in snapshot {
+        match count {
+            Some(n) => writeln!(out, "{} {}", word, n)?,
+            None => writeln!(out, "{} X", word)?,
+        }
+    }
+    Ok(())
+}
+
+/// Read a snapshot written by `write_snapshot`.
+fn read_snapshot(reader: impl BufRead) -> io::Result<Snapshot> {
+    let mut snapshot = BTreeMap::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let bad_line = || io::Error::new(io::ErrorKind::InvalidData,
+            format!("bad snapshot line {:?}", line));
+        let Some((word, count)) = line.split_once(' ') else {
+            return Err(bad_line());
+        };
+        let count = match count.trim() {
+            "X" => None,
+            n => Some(n.parse::<usize>().map_err(|_| bad_line())?),
+        };
+        snapshot.insert(word.to_owned(), count);
+    }
+    Ok(snapshot)
+}
+
+/// The words in both snapshots whose guess count differs, with the count before and after.
+fn compare_snapshots(old: &Snapshot, new: &Snapshot)
+    -> Vec<(String, Option<usize>, Option<usize>)>
+{
+    old.iter()
+        .filter_map(|(word, &before)| {
+            let &after = new.get(word)?;
+            (before != after).then(|| (word.clone(), before, after))
+        })
+        .collect()
+}
+
+/// How many guesses the player gets in --play mode.
+const PLAY_MAX_GUESSES: usize = 6;
+
+/// The most candidates --expected-guesses will build a decision tree for. Playing out every
+/// candidate is too slow for the first turn or two.
+const EXPECTED_GUESSES_MAX_CANDIDATES: usize = 200;
+
+/// Pick a secret word for --play mode, deterministically from the seed.
+fn pick_secret(dictionary: &BTreeSet<String>, seed: u64) -> Option<&String> {
+    if dictionary.is_empty() {
+        return None;
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    dictionary.iter().nth(rng.random_range(0 .. dictionary.len()))
+}
+
+/// Play a game against the given secret word, reading guesses from `input`. Returns the feedback
+/// for each guess made.
+///
+/// With `auto` (a probability and word weights), whenever the most likely word still fitting the
+/// feedback is at least that likely, it's guessed without asking.
+fn play(
+    dictionary: &BTreeSet<String>,
+    secret: &str,
+    color: bool,
+    auto: Option<(f64, &HashMap<String, f64>)>,
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+) -> io::Result<Vec<Vec<Info>>> {
+    let mut rows = vec![];
+    let mut knowledge = Knowledge::new(secret.chars().count());
+    writeln!(out, "Guess the {}-letter word. You get {} guesses.", secret.chars().count(),
+        PLAY_MAX_GUESSES)?;
+    let mut guess_num = 1;
+    while guess_num <= PLAY_MAX_GUESSES {
+        write!(out, "Guess {}/{}: ", guess_num, PLAY_MAX_GUESSES)?;
+        let confident = auto.and_then(|(threshold, weights)| {
+            let candidates = dictionary.iter()
+                .filter(|word| knowledge.check_word(word, false))
+                .collect::<Vec<_>>();
+            candidate_probabilities(&candidates, weights)
+                .into_iter()
+                .next()
+                .filter(|(_, p)| *p >= threshold)
+        });
+        let mut guess = String::new();
+        if let Some((word, p)) = confident {
+            writeln!(out, "{} (auto, {:.0}% likely)", word, p * 100.)?;
+            guess = word;
+        } else {
+            out.flush()?;
+            if input.read_line(&mut guess)? == 0 {
+                break;
+            }
+        }
+        let guess = guess.trim();
+        if !dictionary.contains(guess) {
+            writeln!(out, "{:?} is not in the word list", guess)?;
+            continue;
+        }
+        let infos = check_guess(secret, guess);
+        // The feedback is right by construction, so it always fits.
+        let _ = knowledge.add_infos(&infos, false);
+        writeln!(out, "{}", format_row(&infos, color))?;
+        rows.push(infos);
+        if guess == secret {
+            writeln!(out, "Solved in {} guesses!", guess_num)?;
+            return Ok(rows);
+        }
+        guess_num += 1;
+    }
+    writeln!(out, "Out of guesses. The word was {}.", secret)?;
+    Ok(rows)
+}
+
+/// When to use color, for --color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            other => Err(format!("unknown color mode {:?}", other)),
+        }
+    }
+}
+
+/// Solve several boards at once: suggest a guess, then ask for the feedback from each board which
+/// isn't solved yet, until they all are (or the input runs out).
+fn play_boards(
+    boards: &mut MultiBoard,
+    dictionary: &BTreeSet<String>,
+    letter_freq: &HashMap<char, f64>,
+    num_letters: usize,
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut guess_num = 0;
+    while !boards.all_solved() {
+        guess_num += 1;
+        for (i, (_, candidates)) in boards.boards.iter().enumerate() {
+            if boards.is_solved(i) {
+                writeln!(out, "Board {}: solved", i + 1)?;
+            } else {
+                writeln!(out, "Board {}: {} candidates", i + 1, candidates.len())?;
+            }
+        }
+        match boards.best_guess(dictionary, letter_freq) {
+            Some(guess) => writeln!(out, "Guess {}: {}", guess_num, guess)?,
+            None => {
+                writeln!(out, "no candidates left!")?;
+                return Ok(());
+            }
+        }
+        for board in 0 .. boards.boards.len() {
+            if boards.is_solved(board) {
+                continue;
+            }
+            loop {
+                write!(out, "Feedback for board {}: ", board + 1)?;
+                out.flush()?;
+                let mut line = String::new();
+                if input.read_line(&mut line)? == 0 {
+                    return Ok(());
+                }
+                match parse_input(line.trim(), num_letters).and_then(|infos| {
+                    boards.apply(board, &infos)
+                }) {
+                    Ok(()) => break,
+                    Err(e) => writeln!(out, "Bad input: {}", e)?,
+                }
+            }
+        }
+    }
+    writeln!(out, "All {} boards solved in {} guesses.", boards.boards.len(), guess_num)?;
+    Ok(())
+}
+
+/// Whether to color guesses. With "auto", not if the NO_COLOR environment variable is set, or if
+/// stdout isn't a terminal. --no-color overrides it.
+fn use_color(mode: ColorMode, no_color: bool) -> bool {
+    match mode {
+        _ if no_color => false,
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => std::env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal(),
+    }
+}
+
+/// A row of feedback, either with each letter colored, or as plain text the way it's typed in.
+fn format_row(infos: &[Info], color: bool) -> String {
+    if color {
+        render_infos_colored(infos)
+    } else {
+        format_infos(infos)
+    }
+}
+
+/// Whether a game played with `play` ended with the word being found.
+fn is_won(rows: &[Vec<Info>]) -> bool {
+    rows.last().is_some_and(|infos| infos.iter().all(|info| matches!(info, Info::Exact(_))))
+}
+
+/// After losing a game, show how the solver would have found the word.
+fn reveal_solution(
+    secret: &str,
+    dictionary: &BTreeSet<String>,
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "The solver would have guessed:")?;
+    let guesses = guess_word(secret, dictionary.clone(), None, letter_freq, strategy, false);
+    for (guess, remaining) in guesses {
+        writeln!(out, "  {} {} ({} candidates left)", guess,
+            format_infos(&check_guess(secret, &guess)), remaining)?;
+    }
+    Ok(())
+}
+
+fn print_words<T: AsRef<str>>(msg: &str, words: impl Iterator<Item=T>) {
+    print!("{}: ", msg);
+    let mut it = words.enumerate().peekable();
+    while let Some((i, word)) = it.next() {
+        print!("{}", word.as_ref());
+        if i == 9 {
+            break;
+        }
+        if it.peek().is_some() {
+            print!(", ");
+        }
+    }
+    let cnt = it.count();
+    if cnt > 0 {
+        println!(", and {} more", cnt);
+    } else {
+        println!();
+    }
+}
+
+/// A line for each strategy with the top suggestion it gives, like `entropy: slate`.
+fn compare_strategies<W: AsRef<str>>(
+    strategies: &[Strategy],
+    candidates: &[W],
+    knowledge: &Knowledge,
+    letter_freq: &HashMap<char, f64>,
+) -> Vec<String> {
+    strategies.iter()
+        .map(|strategy| {
+            let best = strategy.rank(candidates, knowledge, letter_freq);
+            format!("{}: {}", strategy.name(), best.first().map(String::as_str).unwrap_or("-"))
+        })
+        .collect()
+}
+
+/// The number of candidates, and if it changed, what it was before and by how much, like
+/// `2315 → 48 (-2267)`.
+fn format_count_change(before: usize, after: usize) -> String {
+    if before == after {
+        after.to_string()
+    } else if after < before {
+        format!("{} \u{2192} {} (-{})", before, after, before - after)
+    } else {
+        format!("{} \u{2192} {} (+{})", before, after, after - before)
+    }
+}
+
+/// One line with the number of candidates and the top few suggestions, like `42: crane slate`.
+fn format_compact<T: AsRef<str>>(num_candidates: usize, best: &[T]) -> String {
+    let mut line = format!("{}:", num_candidates);
+    for word in best.iter().take(3) {
+        line.push(' ');
+        line.push_str(word.as_ref());
+    }
+    line
+}
+
+/// The number of candidates, and then the top suggestions, each on their own line, with nothing
+/// else.
+fn format_quiet<T: AsRef<str>>(num_candidates: usize, best: &[T]) -> String {
+    let mut out = num_candidates.to_string();
+    for word in best.iter().take(10) {
+        out.push('\n');
+        out.push_str(word.as_ref());
+    }
+    out
+}
+
+fn format_anagram_group(word: &str, group_size: usize) -> String {
+    if group_size > 1 {
+        format!("\n\t{} (+{} anagrams)", word, group_size - 1)
+    } else {
+        format!("\n\t{}", word)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A small dictionary for tests which rank or solve real words: some ordinary words, and a few
+    /// groups which differ in only one or two letters, which take the most guesses to tell apart
+    /// (though every word can still be found within six).
+    const FIXTURE_WORDS: &[&str] = &[
+        "crane", "slate", "solid", "fuzzy", "queue", "kayak", "lymph", "chomp",
+        "robot", "motor", "rotor", "tutor", "sorts", "thorn",
+        "batch", "catch", "hatch", "match", "watch",
+        "blimp", "clamp", "plumb", "thumb",
+        "light", "might", "night", "sight", "tight",
+    ];
+
+    /// Words which are mostly hard to tell apart, for tests which solve all of them.
+    const SMALL_FIXTURE_WORDS: &[&str] = &["robot", "motor", "rotor", "tutor", "sorts", "thorn"];
+
+    /// A dictionary of the given words.
+    fn dictionary_of(words: &[&str]) -> BTreeSet<String> {
+        words.iter().map(|&w| w.to_owned()).collect()
+    }
+
+    /// The fixture dictionary, with its letter frequencies.
+    fn fixture() -> (BTreeSet<String>, HashMap<char, f64>) {
+        let dictionary = dictionary_of(FIXTURE_WORDS);
+        let letter_freq = letter_frequencies(&dictionary);
+        (dictionary, letter_freq)
+    }
+
+    /// The small fixture dictionary, with its letter frequencies.
+    fn small_fixture() -> (BTreeSet<String>, HashMap<char, f64>) {
+        let dictionary = dictionary_of(SMALL_FIXTURE_WORDS);
+        let letter_freq = letter_frequencies(&dictionary);
+        (dictionary, letter_freq)
+    }
+
+    #[test]
+    fn test_fixture_solves_every_word() {
+        let (dictionary, letter_freq) = fixture();
+        for strategy in [Strategy::Frequency, Strategy::Entropy, Strategy::ADAPTIVE] {
+            for word in &dictionary {
+                let guesses = guess_word(word, dictionary.clone(), None, &letter_freq, &strategy,
+                    false);
+                assert_eq!(&guesses.last().unwrap().0, word, "{} with {:?}", word, strategy);
+                assert!(guesses.len() <= PLAY_MAX_GUESSES, "{} with {:?}: {:?}", word, strategy,
+                    guesses);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixture_ranking() {
+        let (dictionary, letter_freq) = fixture();
+        let mut knowledge = Knowledge::new(5);
+        let best = best_candidates(dictionary.iter(), &knowledge, &letter_freq);
+        assert!(best.len() >= 10);
+        // Words with repeated letters only come after all the ones without.
+        let unique = best.iter().map(|w| unique_letters(w)).collect::<Vec<_>>();
+        assert!(unique.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", best);
+        assert!(!best.iter().any(|w| ["fuzzy", "queue", "kayak"].contains(&w.as_str())));
+
+        knowledge.add_infos(&check_guess("hatch", "crane"), false).unwrap();
+        let candidates = dictionary.iter()
+            .filter(|w| knowledge.check_word(w, false))
+            .collect::<Vec<_>>();
+        assert_eq!(candidates, ["batch", "hatch", "match", "watch"]);
+        for strategy in [Strategy::Frequency, Strategy::Entropy, Strategy::Lookahead] {
+            let ranked = strategy.rank(&candidates, &knowledge, &letter_freq);
+            assert!(ranked.iter().all(|w| candidates.contains(&w)), "{:?}", strategy);
+        }
+        let ranked = Strategy::Entropy.rank_probes(FIXTURE_WORDS, &candidates, &knowledge,
+            &letter_freq);
+        // A word which isn't a candidate tells the most "-atch" words apart.
+        assert!(!candidates.contains(&&ranked[0]), "{:?}", ranked);
+    }
+
+    #[test]
+    fn test_5() -> Result<(), SolveError> {
+        use Info::*;
+        let mut k = Knowledge::new(5);
+        k.add_infos(&[
+            No('a'),
+            No('d'),
+            No('i'),
+            No('e'),
+            No('u'),
+        ], true)?;
+        assert!(k.check_word("thorn", true));
+        k.add_infos(&[
+            Somewhere('t'),
+            No('h'),
+            Somewhere('o'),
+            Somewhere('r'),
+            No('n'),
+        ], true)?;
+        assert!(k.check_word("sorts", true));
+        k.add_infos(&[
+            No('s'),
+            Exact('o'),
+            Somewhere('r'),
+            Somewhere('t'),
+            No('s'),
+        ], true)?;
+        assert!(!k.check_word("palmy", true));
+        k.add_infos(&[
+            No('p'),
+            No('a'),
+            No('l'),
+            No('m'),
+            No('y'),
+        ], true)?;
+        eprintln!("{:#?}", k);
+        assert!(k.check_word("robot", true));
+        assert!(!k.check_word("motor", true));
+        Ok(())
+    }
+
+    #[test]
+    fn test_11_1() -> Result<(), SolveError> {
+        use Info::*;
+        let mut k = Knowledge::new(11);
+        // !u?l*c?e?r?a!t!i*o!n!s
+        k.add_infos(&[
+            No('u'),
+            Somewhere('l'),
+            Exact('c'),
+            Somewhere('e'),
+            Somewhere('r'),
+            Somewhere('a'),
+            No('t'),
+            No('i'),
+            Exact('o'),
+            No('n'),
+            No('s'),
+        ], true)?;
+        assert!(k.check_word("archaeology", true));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() {
+        use Info::*;
+        assert_eq!(parse_input("!u?l*c?e?r?a!t!i*o!n!s", 11),
+            Ok(vec![
+                No('u'),
+                Somewhere('l'),
+                Exact('c'),
+                Somewhere('e'),
+                Somewhere('r'),
+                Somewhere('a'),
+                No('t'),
+                No('i'),
+                Exact('o'),
+                No('n'),
+                No('s'),
+            ]));
+    }
+
+    #[test]
+    fn test_11_2() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(11);
+        k.add_infos(&parse_input("?u!l*c!e?r!a!t?i*o?n*s", 11)?, true)?;
+        assert!(k.check_word("incongruous", true));
+        Ok(())
+    }
+
+    #[test]
+    fn test_11_3() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(11);
+        // symptomatic / masochistic
+        k.add_infos(&parse_input("!u!l?c!e!r?a?t?i?o!n?s", 11)?, true)?;
+        assert!(k.check_word("symptomatic", true));
+        assert!(k.check_word("masochistic", true));
+        k.add_infos(&parse_input("?s!y?m!p!t?o!m?a*t*i*c", 11)?, true)?;
+        assert!(!k.check_word("symptomatic", true));
+        assert!(k.check_word("masochistic", true));
+        Ok(())
+    }
+
+    #[test]
+    fn test_11_4() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(11);
+        // symptomatic / masochistic
+        k.add_infos(&parse_input("!u!l?c!e!r?a?t?i?o!n?s", 11)?, true)?;
+        assert!(k.check_word("symptomatic", true));
+        assert!(k.check_word("masochistic", true));
+        k.add_infos(&parse_input("?m?a?s?o!c!h!i!s*t*i*c", 11)?, true)?;
+        assert!(k.check_word("symptomatic", true));
+        assert!(!k.check_word("masochistic", true));
+        Ok(())
+    }
+
+    #[test]
+    fn test_anagram_representative() {
+        assert_eq!(anagram_key("stare"), "aerst");
+        assert_eq!(anagram_key("tears"), anagram_key("rates"));
+
+        // However the tied anagrams happen to be ordered, the same representative is chosen.
+        let orders: &[&[&str]] = &[
+            &["stare", "tears", "aster", "irate", "rates"],
+            &["rates", "aster", "tears", "irate", "stare"],
+            &["tears", "irate", "stare", "rates", "aster"],
+        ];
+        for words in orders {
+            let groups = collapse_anagrams(words);
+            assert_eq!(groups.len(), 2);
+            assert!(groups.contains(&("aster".to_owned(), 4)));
+            assert!(groups.contains(&("irate".to_owned(), 1)));
+
+            // When the order means something, the best-ranked of each group stands for it.
+            let groups = collapse_anagrams_ranked(words);
+            assert_eq!(groups.len(), 2);
+            assert_eq!(groups.iter().find(|(_, n)| *n == 4).unwrap().0, words[0]);
+        }
+    }
+
+    #[test]
+    fn test_key_status() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(5);
+        k.add_infos(&parse_input("*c?r!a!n!e", 5)?, false)?;
+        assert_eq!(k.key_status('c'), KeyStatus::Placed);
+        assert_eq!(k.key_status('r'), KeyStatus::Present);
+        assert_eq!(k.key_status('a'), KeyStatus::Absent);
+        assert_eq!(k.key_status('e'), KeyStatus::Absent);
+        assert_eq!(k.key_status('z'), KeyStatus::Unknown);
+
+        let keyboard = render_keyboard(&k);
+        assert_eq!(keyboard.lines().count(), 3);
+        assert!(keyboard.contains("\x1b[30;42m c \x1b[0m"));
+        assert!(keyboard.contains("\x1b[30;43m r \x1b[0m"));
+        assert!(keyboard.contains("\x1b[37;100m a \x1b[0m"));
+        assert!(keyboard.contains(" z "));
+        Ok(())
+    }
+
+    #[test]
+    fn test_gray_only_positional() -> Result<(), SolveError> {
+        let infos = parse_input("!e*r?a!s!e", 5)?;
+
+        let mut strict = Knowledge::new(5);
+        strict.add_infos(&infos, true)?;
+        assert!(!strict.check_word("aries", true));
+
+        let mut lenient = Knowledge::new(5).with_gray_means_absent(false);
+        lenient.add_infos(&infos, true)?;
+        // 'e' and 's' were gray, but only rule themselves out of the positions they were in.
+        assert!(lenient.check_word("aries", true));
+        assert!(lenient.check_word("areas", true));
+        assert!(!lenient.check_word("erase", true));
+        assert!(!lenient.check_word("arass", true));
+        Ok(())
+    }
+
+    #[test]
+    fn test_best_guess_among() {
+        let candidates = ["batch", "catch", "hatch", "latch", "match", "patch"];
+
+        // "batch" and "latch" only single out themselves; "chomp" splits the rest up better.
+        let probes = ["batch", "latch", "chomp"];
+        for metric in [SplitMetric::Entropy, SplitMetric::ExpectedRemaining, SplitMetric::WorstCase] {
+            assert_eq!(best_guess_among(&probes, &candidates, metric).as_deref(), Some("chomp"));
+        }
+
+        // Between equal splitters, the first one wins, unless a later one could be the answer.
+        assert_eq!(best_guess_among(&["zzzzz", "latch"], &candidates[1..2], SplitMetric::Entropy)
+            .as_deref(), Some("zzzzz"));
+        assert_eq!(best_guess_among(&["zzzzz", "catch"], &candidates[1..2], SplitMetric::Entropy)
+            .as_deref(), Some("catch"));
+
+        assert_eq!(best_guess_among(&probes, &[], SplitMetric::Entropy), None);
+        assert_eq!(best_guess_among(&[], &candidates, SplitMetric::Entropy), None);
+    }
+
+    #[test]
+    fn test_interrupt_summary() {
+        assert_eq!(interrupt_summary(Some("crane"), 42),
+            "Interrupted with 42 candidates left. Best guess: crane");
+        assert_eq!(interrupt_summary(None, 0), "Interrupted with 0 candidates left.");
+    }
+
+    #[test]
+    fn test_read_csv_dictionary() -> io::Result<()> {
+        let csv = "word,count\ncrane,120\nslate, 80\nxylyl,1\n";
+        let (words, weights) = read_dictionary(csv.as_bytes(), None)?;
+        assert_eq!(words, ["crane", "slate", "xylyl"]);
+        assert_eq!(weights.len(), 3);
+        assert_eq!(weights["crane"], 120.);
+        assert_eq!(weights["slate"], 80.);
+        assert_eq!(weights["xylyl"], 1.);
+
+        // Explicit format, without a header.
+        let (words, weights) = read_dictionary("crane,3\n".as_bytes(), Some(DictFormat::Csv))?;
+        assert_eq!(words, ["crane"]);
+        assert_eq!(weights["crane"], 3.);
+
+        let (words, weights) = read_dictionary("crane\nslate\n".as_bytes(), None)?;
+        assert_eq!(words, ["crane", "slate"]);
+        assert!(weights.is_empty());
+
+        assert!(read_dictionary("crane,lots\n".as_bytes(), Some(DictFormat::Csv)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_self_check() {
+        let (dictionary, letter_freq) = small_fixture();
+        let openers = ["sorts".to_owned()];
+        for word in &dictionary {
+            for strategy in [Strategy::Frequency, Strategy::Entropy, Strategy::Lookahead] {
+                let guesses = guess_word_checked(word, &[], dictionary.clone(), None, &letter_freq,
+                    &strategy, false, None);
+                let unchecked = guess_word(word, dictionary.clone(), None, &letter_freq,
+                    &strategy, false);
+                assert_eq!(guesses, Ok(unchecked), "{} {:?}", word, strategy);
+
+                let guesses = guess_word_checked(word, &openers, dictionary.clone(), None,
+                    &letter_freq, &strategy, true, Some(2)).unwrap();
+                assert_eq!(guesses[0].0, "sorts");
+                assert!(guesses.len() <= 3, "{} {:?}: {:?}", word, strategy, guesses);
+            }
+        }
+
+        let ruled_out = SolveError::RuledOut {
+            word: "robot".to_owned(),
+            guesses: vec!["crane".to_owned(), "sorts".to_owned()],
+        };
+        assert_eq!(ruled_out.to_string(), "robot was ruled out after guessing crane, sorts");
+    }
+
+    #[test]
+    fn test_tree_dot() -> io::Result<()> {
+        let words = ["batch", "catch", "hatch", "latch", "match", "patch"];
+        let letter_freq = letter_frequencies(words);
+        let tree = build_decision_tree("chomp", &words, &Knowledge::new(5), &letter_freq,
+            &Strategy::Frequency);
+        assert_eq!(tree.candidates.len(), words.len());
+
+        let mut dot = vec![];
+        write_dot(&tree, &mut dot)?;
+        let dot = String::from_utf8(dot).unwrap();
+
+        assert!(dot.starts_with("digraph decision_tree {\n"), "{}", dot);
+        assert!(dot.ends_with("}\n"), "{}", dot);
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count(), "{}", dot);
+        assert_eq!(dot.matches('"').count() % 2, 0, "{}", dot);
+
+        // Every edge connects nodes which were declared.
+        let nodes = dot.lines()
+            .filter(|l| l.contains("[label=") && !l.contains("->"))
+            .map(|l| l.split_whitespace().next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(nodes[0], "n0");
+        for edge in dot.lines().filter(|l| l.contains("->")) {
+            let parts = edge.split_whitespace().collect::<Vec<_>>();
+            assert!(nodes.contains(&parts[0]) && nodes.contains(&parts[2]), "{}\n{}", edge, dot);
+        }
+        // Every word gets solved somewhere in the tree.
+        for word in words {
+            assert!(dot.contains(&format!("\"{}\\n", word)), "{}\n{}", word, dot);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefer_ergonomic() {
+        assert_eq!(["tract", "minor", "stair", "fuzzy", "queen"].map(same_hand_rolls),
+            [4, 3, 2, 1, 1]);
+
+        // None of the last three have any letters from the candidates, so they're all equally
+        // uninformative, but some are easier to type.
+        let candidates = ["fuzzy", "queue"];
+        let mut suggestions = vec!["fuzzy", "queen", "tract", "minor", "stair"];
+        prefer_ergonomic(&mut suggestions, &candidates);
+        assert_eq!(suggestions, ["fuzzy", "queen", "stair", "minor", "tract"]);
+
+        // Only ties next to each other are reordered; otherwise the ranking stands.
+        let mut suggestions = vec!["tract", "fuzzy", "stair"];
+        prefer_ergonomic(&mut suggestions, &["fuzzy", "crane"]);
+        assert_eq!(suggestions, ["tract", "fuzzy", "stair"]);
+    }
+
+    #[test]
+    fn test_demote_uninformative() {
+        let candidates = ["batch", "catch", "hatch"];
+        assert!(!is_informative("zzzzz", &candidates));
+        assert!(!is_informative("latch", &candidates));
+        assert!(is_informative("chomp", &candidates));
+        assert!(is_informative("batch", &candidates));
+
+        let mut suggestions = vec!["zzzzz", "latch", "chomp", "batch"];
+        demote_uninformative(&mut suggestions, &candidates);
+        assert_eq!(suggestions, ["chomp", "batch", "zzzzz", "latch"]);
+
+        // With only one candidate left, nothing is informative, so nothing moves.
+        let mut suggestions = vec!["zzzzz", "batch"];
+        demote_uninformative(&mut suggestions, &candidates[..1]);
+        assert_eq!(suggestions, ["zzzzz", "batch"]);
+    }
+
+    #[test]
+    fn test_candidate_probabilities() {
+        let weights = [("slate", 34.), ("least", 22.), ("stale", 4.), ("other", 1000.)]
+            .iter().map(|(w, n)| (w.to_string(), *n)).collect::<HashMap<_, _>>();
+        let candidates = ["least", "slate", "stale", "steal"];
+        let probs = candidate_probabilities(&candidates, &weights);
+        assert_eq!(probs.iter().map(|(w, _)| w.as_str()).collect::<Vec<_>>(),
+            ["slate", "least", "stale", "steal"]);
+        assert!((probs.iter().map(|(_, p)| p).sum::<f64>() - 1.).abs() < 1e-9);
+        assert!((probs[0].1 - 34. / 60.).abs() < 1e-9);
+        assert_eq!(probs[3].1, 0.);
+
+        // Without weights, they're all equally likely.
+        let probs = candidate_probabilities(&candidates, &HashMap::new());
+        assert!((probs.iter().map(|(_, p)| p).sum::<f64>() - 1.).abs() < 1e-9);
+        assert!(probs.iter().all(|(_, p)| *p == 0.25));
+    }
+
+    #[test]
+    fn test_format_compact() {
+        assert_eq!(format_compact(2315, &["raise", "arise", "irate", "orate"]), "2315: raise arise irate");
+        assert_eq!(format_compact(2, &["robot", "motor"]), "2: robot motor");
+        assert_eq!(format_compact(0, &[] as &[&str]), "0:");
+    }
+
+    #[test]
+    fn test_equation_validity() -> Result<(), SolveError> {
+        // Nerdle-style: "words" are equations like "3+4=7", which must balance.
+        fn balances(eq: &str) -> bool {
+            let Some((lhs, rhs)) = eq.split_once('=') else { return false };
+            let Some(op_idx) = lhs.find(['+', '-', '*', '/']) else { return false };
+            let (Ok(a), Ok(b), Ok(c)) = (
+                lhs[.. op_idx].parse::<i64>(),
+                lhs[op_idx + 1 ..].parse::<i64>(),
+                rhs.parse::<i64>(),
+            ) else {
+                return false;
+            };
+            match &lhs[op_idx .. op_idx + 1] {
+                "+" => a + b == c,
+                "-" => a - b == c,
+                "*" => a * b == c,
+                _ => b != 0 && a % b == 0 && a / b == c,
+            }
+        }
+
+        let mut k = Knowledge::new(5).with_validity(balances);
+        let equations = ["1+2=3", "1+1=3", "3+4=7", "4+3=7", "9-5=4", "2*3=6", "8/2=4", "12+34", "abcde"]
+            .iter()
+            .filter(|eq| k.check_word(eq, true))
+            .map(|eq| eq.to_string())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(equations.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["1+2=3", "2*3=6", "3+4=7", "4+3=7", "8/2=4", "9-5=4"]);
+
+        // The usual feedback machinery works on any characters.
+        let infos = check_guess("3+4=7", "1+2=3");
+        assert_eq!(infos, parse_input("!1*+!2*=?3", 5)?);
+        k.add_infos(&infos, true)?;
+        let remaining = equations.iter().filter(|eq| k.check_word(eq, true)).collect::<Vec<_>>();
+        assert_eq!(remaining, ["3+4=7", "4+3=7"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_play_seed() -> io::Result<()> {
+        let dictionary = BufReader::new(File::open("answers.txt")?)
+            .lines()
+            .collect::<io::Result<BTreeSet<String>>>()?;
+        let secret = pick_secret(&dictionary, 1234).unwrap();
+        assert_eq!(pick_secret(&dictionary, 1234).unwrap(), secret);
+        assert_eq!(pick_secret(&dictionary.clone(), 1234).unwrap(), secret);
+        assert!((0 .. 10).any(|seed| pick_secret(&dictionary, seed).unwrap() != secret));
+        assert_eq!(pick_secret(&BTreeSet::new(), 1234), None);
+
+        let mut out = vec![];
+        let rows = play(&dictionary, secret, false, None,
+            &mut format!("xxxxx\nraise\n{}\n", secret).as_bytes(), &mut out)?;
+        let won = is_won(&rows);
+        let out = String::from_utf8(out).unwrap();
+        assert!(won || secret == "raise");
+        assert!(out.contains("\"xxxxx\" is not in the word list"));
+        assert!(out.contains(&format_infos(&check_guess(secret, "raise"))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_play_loss_reveal() -> io::Result<()> {
+        let (dictionary, letter_freq) = small_fixture();
+
+        let mut out = vec![];
+        let guesses = "sorts\n".repeat(PLAY_MAX_GUESSES);
+        assert!(!is_won(&play(&dictionary, "robot", false, None, &mut guesses.as_bytes(),
+            &mut out)?));
+        reveal_solution("robot", &dictionary, &letter_freq, &Strategy::Frequency, &mut out)?;
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("The word was robot."), "{}", out);
+
+        // The revealed path is the solver's, and it ends with the secret.
+        let path = out.lines()
+            .skip_while(|l| !l.starts_with("The solver would have guessed:"))
+            .skip(1)
+            .map(|l| l.split_whitespace().next().unwrap())
+            .collect::<Vec<_>>();
+        let expected = guess_word("robot", dictionary.clone(), None, &letter_freq,
+            &Strategy::Frequency, false);
+        assert_eq!(path, expected.iter().map(|(g, _)| g.as_str()).collect::<Vec<_>>(), "{}", out);
+        assert_eq!(path.last(), Some(&"robot"), "{}", out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_knowledge_diff() -> Result<(), SolveError> {
+        let infos = parse_input("*c?r!a!n!e", 5)?;
+        let mut k = Knowledge::new(5);
+        let diff = k.diff(&infos)?;
+        assert_eq!(diff.new_exact, [(0, 'c')]);
+        assert!(diff.new_excluded.contains(&(1, 'r')));
+        assert!(diff.new_excluded.contains(&(4, 'a')));
+        assert!(!diff.new_excluded.contains(&(0, 'a')));
+        assert_eq!(diff.raised_counts, [('c', 1), ('r', 1)]);
+
+        // Diffing doesn't change anything.
+        assert!(k.check_word("cyrus", true));
+
+        // Once it's been applied, applying it again teaches us nothing.
+        k.add_infos(&infos, true)?;
+        assert!(k.diff(&infos)?.is_empty());
+
+        let diff = k.diff(&parse_input("*c!h?o*r!d", 5)?)?;
+        assert_eq!(diff.new_exact, [(3, 'r')]);
+        assert_eq!(diff.raised_counts, [('o', 1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_frequency_ranking() {
+        let words = ["eerie", "trace", "aback", "crate", "aloft", "geese", "react", "abbey"];
+        let best = best_candidates(words.iter(), &Knowledge::new(5), &HashMap::new());
+        assert_eq!(best, [
+            // 5 unique letters
+            &"aloft", &"crate", &"react", &"trace",
+            // 4 unique letters
+            &"aback", &"abbey",
+            // 3 unique letters
+            &"eerie", &"geese",
+        ]);
+    }
+
+    #[test]
+    fn test_adaptive_strategy() {
+        let adaptive = "adaptive".parse::<Strategy>().unwrap();
+        assert_eq!(adaptive.for_pool_size(12972), Strategy::Frequency);
+        assert_eq!(adaptive.for_pool_size(2315), Strategy::Frequency);
+        assert_eq!(adaptive.for_pool_size(1000), Strategy::Frequency);
+        assert_eq!(adaptive.for_pool_size(999), Strategy::Entropy);
+        assert_eq!(adaptive.for_pool_size(168), Strategy::Entropy);
+        assert_eq!(adaptive.for_pool_size(50), Strategy::Entropy);
+        assert_eq!(adaptive.for_pool_size(49), Strategy::Lookahead);
+        assert_eq!(adaptive.for_pool_size(2), Strategy::Lookahead);
+
+        let custom = Strategy::Adaptive { entropy_below: 100, lookahead_below: 10 };
+        assert_eq!(custom.for_pool_size(500), Strategy::Frequency);
+        assert_eq!(custom.for_pool_size(50), Strategy::Entropy);
+        assert_eq!(custom.for_pool_size(5), Strategy::Lookahead);
+
+        assert_eq!(Strategy::Entropy.for_pool_size(12972), Strategy::Entropy);
+        assert!("bogus".parse::<Strategy>().is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse_input("*a*b*c*d*e*f", 5), Err(SolveError::TooManyLetters));
+        assert_eq!(parse_input("*a#b", 5), Err(SolveError::UnknownFlag('#')));
+        assert_eq!(parse_input("*a!", 5), Err(SolveError::UnprocessedInput('!')));
+
+        let mut k = Knowledge::new(5);
+        k.add_infos(&parse_input("*c!r!a!n!e", 5).unwrap(), true).unwrap();
+        let err = k.add_infos(&parse_input("*s!l!a!t!e", 5).unwrap(), true).unwrap_err();
+        assert_eq!(err, KnowledgeError::ConflictingExact { position: 0, existing: 'c', new: 's' });
+        assert_eq!(err.to_string(), "you already said that letter 0 is 'c', not 's'");
+        assert_eq!(SolveError::from(err.clone()).to_string(), err.to_string());
+        let err = k.add_infos(&parse_input("?c!l!a!t!e", 5).unwrap(), true).unwrap_err();
+        assert_eq!(err, KnowledgeError::ExactPositionIsYellow { position: 0, letter: 'c' });
+        assert_eq!(err.to_string(), "you already said that letter 0 is 'c'");
+        // A yellow for some other letter is fine, though: it's somewhere else.
+        assert_eq!(k.add_infos(&parse_input("?s!l!a!t!e", 5).unwrap(), true), Ok(()));
+        assert_eq!(k.add_infos(&parse_input("!a!b!c!d!e!f", 6).unwrap(), true),
+            Err(KnowledgeError::LengthMismatch { expected: 5, actual: 6 }));
+    }
+
+    #[test]
+    fn test_sample_words() {
+        let dictionary = (0 .. 500).map(|i| format!("w{:03}", i)).collect::<BTreeSet<_>>();
+        let sample = sample_words(&dictionary, 25, 42);
+        assert_eq!(sample.len(), 25);
+        assert_eq!(sample.iter().collect::<BTreeSet<_>>().len(), 25);
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(sample_words(&dictionary, 25, 42), sample);
+        assert_ne!(sample_words(&dictionary, 25, 43), sample);
+        assert_eq!(sample_words(&dictionary, 1000, 42).len(), 500);
+
+        for stride in [1, 3, 7, 500, 501] {
+            let words = every_nth(&dictionary, stride);
+            assert_eq!(words.len(), 500usize.div_ceil(stride));
+            assert_eq!(words[0], "w000");
+            assert!(words.windows(2).all(|w| w[0] < w[1]));
+        }
+        assert_eq!(every_nth(&dictionary, 7)[1..3], ["w007", "w014"]);
+
+        let results = vec![
+            vec![("raise".to_owned(), 10), ("robot".to_owned(), 1)],
+            vec![("raise".to_owned(), 10), ("motor".to_owned(), 2), ("rotor".to_owned(), 1)],
+            vec![("raise".to_owned(), 10), (String::new(), 0)],
+        ];
+        let summary = Summary::new(&results);
+        assert_eq!(summary, Summary {
+            words: 3,
+            mean: 2.5,
+            median: 2.5,
+            std_dev: 0.5,
+            max: 3,
+            counts: [(2, 1), (3, 1)].into_iter().collect(),
+            failures: 1,
+        });
+        assert_eq!(summary.to_string(), "3 words: 2.500 guesses on average (median 2.5, std dev \
+            0.500), 3 at most, 1 failed\nguesses (words): 2 (1), 3 (1)");
+    }
+
+    #[test]
+    fn test_first_letter_stats() {
+        // Guessing "zzzzz" narrows nothing down, so each word leaves all of them.
+        let words = ["batch", "catch", "crane", "hatch"];
+        assert_eq!(first_letter_stats(&words, "zzzzz"), [
+            ('b', 1, 4.),
+            ('c', 2, 4.),
+            ('h', 1, 4.),
+        ]);
+
+        // "chomp" singles out "catch" and "crane", but "batch" and "hatch" look the same.
+        assert_eq!(first_letter_stats(&words, "chomp"), [
+            ('b', 1, 2.),
+            ('h', 1, 2.),
+            ('c', 2, 1.),
+        ]);
+    }
+
+    #[test]
+    fn test_guess_from() {
+        let candidates = ["batch", "catch", "hatch", "latch", "match", "patch"];
+        let letter_freq = letter_frequencies(candidates);
+        // Includes letters that aren't in any candidate.
+        let probes = ["bunny", "chomp", "zzzzz", "latch"];
+        for strategy in [Strategy::Frequency, Strategy::Entropy, Strategy::Lookahead] {
+            let best = strategy.rank_probes(&probes, &candidates, &Knowledge::new(5), &letter_freq);
+            assert!(!best.is_empty());
+            assert!(best.iter().all(|w| probes.contains(&w.as_str())), "{:?} {:?}", strategy, best);
+        }
+        let best = Strategy::Entropy.rank_probes(&probes, &candidates, &Knowledge::new(5),
+            &letter_freq);
+        assert_eq!(best[0], "chomp");
+    }
+
+    #[test]
+    fn test_reload_dictionary() -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("wordle-reload-{}.txt", std::process::id()));
+        std::fs::write(&path, "robot\nmotor\nsorts\nthornier\n")?;
+        let mut knowledge = Knowledge::new(5);
+        knowledge.add_infos(&parse_input("?t!h?o?r!n", 5)?, false)?;
+
+        let load = || load_dictionary(BufReader::new(File::open(&path)?), None, 5);
+        let (dictionary, _) = load()?;
+        assert_eq!(dictionary.words().len(), 3);
+        let candidates = dictionary.filter(&knowledge);
+        assert_eq!(candidates.words().iter().collect::<Vec<_>>(), ["motor", "robot", "sorts"]);
+
+        std::fs::write(&path, "robot\nmotor\nsorts\nthornier\nrotor\ncrane\n")?;
+        let (reloaded, _) = load()?;
+        let freq = reloaded.letter_freq();
+        assert!(freq.contains_key(&'c'));
+        assert!((freq.values().sum::<f64>() - 1.).abs() < 1e-9);
+        let candidates = reloaded.filter(&knowledge);
+        assert_eq!(candidates.words().iter().collect::<Vec<_>>(),
+            ["motor", "robot", "rotor", "sorts"]);
+        // The frequencies are still those of the whole dictionary.
+        assert_eq!(candidates.letter_freq(), freq);
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_after_inferring() -> Result<(), SolveError> {
+        let mut entered = Knowledge::new(5);
+        entered.add_infos(&parse_input("?t!h?o?r!n", 5)?, false)?;
+
+        // All of these have 'o' second, so that gets taken as green.
+        let (candidates, knowledge) =
+            reload_candidates(dictionary_of(&["motor", "robot", "sorts", "crane"]), &entered);
+        assert_eq!(candidates, dictionary_of(&["motor", "robot", "sorts"]));
+        assert!(!knowledge.check_word("actor", false));
+
+        // But "actor" fits everything entered, so it's a candidate once it's in the dictionary.
+        let (candidates, knowledge) = reload_candidates(
+            dictionary_of(&["motor", "robot", "sorts", "crane", "actor"]), &entered);
+        assert_eq!(candidates, dictionary_of(&["actor", "motor", "robot", "sorts"]));
+        assert!(knowledge.check_word("actor", false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_with_answers() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir();
+        let dict_path = dir.join(format!("wordle-reload-dict-{}.txt", std::process::id()));
+        let answers_path = dir.join(format!("wordle-reload-answers-{}.txt", std::process::id()));
+        std::fs::write(&dict_path, "robot\nsorts\ncrane\n")?;
+        std::fs::write(&answers_path, "motor\nrobot\n")?;
+        let args = Args::from_iter_safe([
+            "wordle-solve".as_ref(),
+            "--answers-path".as_ref(),
+            answers_path.as_os_str(),
+            "5".as_ref(),
+            dict_path.as_os_str(),
+        ])?;
+
+        // Reloading goes the same way as loading at startup: the answers can be guessed too.
+        let load = || load_words(BufReader::new(File::open(&dict_path)?), &args);
+        for _ in 0 .. 2 {
+            let (dictionary, _, answers) = load()?;
+            assert_eq!(dictionary.words().iter().collect::<Vec<_>>(),
+                ["crane", "motor", "robot", "sorts"]);
+            assert_eq!(answers.unwrap().into_iter().collect::<Vec<_>>(), ["motor", "robot"]);
+        }
+
+        std::fs::remove_file(&dict_path)?;
+        std::fs::remove_file(&answers_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_dictionary() {
+        let words = ["crane", "Robot", "motor", "thornier", "slate", "motor"];
+        let mut dictionary = Dictionary::from_words(words.map(str::to_owned), 5);
+        assert_eq!(dictionary.words(), &dictionary_of(&["crane", "motor", "slate"]));
+        assert_eq!(dictionary.letter_freq(), &letter_frequencies(dictionary.words()));
+
+        dictionary.extend(["robot".to_owned()]);
+        assert_eq!(dictionary.words().len(), 4);
+        assert_eq!(dictionary.letter_freq(), &letter_frequencies(dictionary.words()));
+
+        let dictionary = dictionary.with_frequency(false);
+        assert!(dictionary.letter_freq().is_empty());
+        let (words, letter_freq) = dictionary.with_frequency(true).into_parts();
+        assert_eq!(letter_freq, letter_frequencies(&words));
+    }
+
+    #[test]
+    fn test_expected_guesses() {
+        let words = ["batch", "catch", "hatch", "latch", "match", "patch"];
+        let letter_freq = letter_frequencies(words);
+        for strategy in [Strategy::Frequency, Strategy::Entropy, Strategy::Lookahead] {
+            let tree = build_decision_tree("chomp", &words, &Knowledge::new(5), &letter_freq,
+                &strategy);
+            let counts = tree.guesses_per_answer();
+            assert_eq!(counts.len(), words.len());
+            // "chomp" isn't an answer, so everything takes at least two guesses. Its three-way
+            // split means at most two more are needed after that.
+            assert!(counts.iter().all(|(_, n)| (2 ..= 4).contains(n)), "{:?}", counts);
+            let expected = tree.expected_guesses();
+            assert!((2. ..= 3.5).contains(&expected), "{:?} {}", strategy, expected);
+        }
+
+        // With one candidate left, it's a single guess.
+        let tree = build_decision_tree("catch", &["catch"], &Knowledge::new(5), &letter_freq,
+            &Strategy::Frequency);
+        assert_eq!(tree.expected_guesses(), 1.);
+    }
+
+    #[test]
+    fn test_letters_pattern() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(5);
+        assert_eq!(k.letters_pattern(), "_ _ _ _ _");
+        k.add_infos(&parse_input("!s*o?r!t!s", 5)?, true)?;
+        assert_eq!(k.letters_pattern(), "_ o _ _ _ (also: r)");
+        k.add_infos(&parse_input("!c*o!u?r*t", 5)?, true)?;
+        assert_eq!(k.letters_pattern(), "_ o _ _ t (also: r)");
+        k.add_infos(&parse_input("*r*o*b*o*t", 5)?, true)?;
+        assert_eq!(k.letters_pattern(), "r o b o t");
+        Ok(())
+    }
+
+    #[test]
+    fn test_recent_letter_penalty() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(5).with_gray_means_absent(false);
+        k.add_infos(&parse_input("!c!r!a!n!e", 5)?, false)?;
+        let recent = ['c', 'r', 'a', 'n', 'e'];
+        // Apart from the letters just tried, "stare" and "stomp" score the same.
+        let letter_freq = [('s', 0.3), ('t', 0.3), ('a', 0.1), ('r', 0.1), ('e', 0.1)]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let words = ["stare", "stomp"];
+        let best = best_candidates_avoiding(words.iter().copied(), &k, &letter_freq, &recent, 0.,
+            false);
+        assert_eq!(best, ["stare", "stomp"]);
+        let best = best_candidates_avoiding(words.iter().copied(), &k, &letter_freq, &recent, 1.,
+            false);
+        assert_eq!(best, ["stomp", "stare"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_histogram() {
+        let solved = |n: usize| (0 .. n).map(|i| (format!("guess{}", i), 1)).collect::<Vec<_>>();
+        let results = vec![
+            solved(2), solved(3), solved(3), solved(3), solved(3), solved(3), solved(3),
+            solved(3), solved(3), solved(4), solved(4), solved(4), solved(4),
+            vec![("guess0".to_owned(), 5), (String::new(), 5)],
+        ];
+        // 1 + 3 + 1 + 1 columns of label, separator and count leave 24 for the bars.
+        assert_eq!(render_histogram(&results, 30), "\
+1 |  0
+2 | ### 1
+3 | ######################## 8
+4 | ############ 4
+X | ### 1
+");
+    }
+
+    #[test]
+    fn test_forbid_positions() -> Result<(), SolveError> {
+        let (c, positions) = parse_not_command("e 1 4").unwrap();
+        assert_eq!((c, positions.as_slice()), ('e', [0, 3].as_slice()));
+        assert!(parse_not_command("e 0").is_err());
+        assert!(parse_not_command("ex 1").is_err());
+
+        let mut k = Knowledge::new(5);
+        k.forbid_positions(c, &positions)?;
+        let words = ["eagle", "steer", "crane", "ocean"];
+        let left = words.iter().filter(|w| k.check_word(w, false)).collect::<Vec<_>>();
+        // Nothing is said about "e" being in the word, so "ocean" still counts.
+        assert_eq!(left, [&"crane", &"ocean"]);
+
+        k.add_infos(&parse_input("*c!r!a!n!e", 5)?, false)?;
+        assert_eq!(k.forbid_positions('c', &[1, 0]),
+            Err(KnowledgeError::ExactPositionIsYellow { position: 0, letter: 'c' }));
+        assert_eq!(k.forbid_positions('x', &[1, 5]),
+            Err(KnowledgeError::PositionOutOfRange { position: 5, num_letters: 5 }));
+        // Neither failure changed anything.
+        assert!(k.check_word("ccxxx", false));
+        assert!(k.check_word("cxxxx", false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_share() {
+        let rows = ["crane", "moist", "robot"].iter()
+            .map(|guess| check_guess("robot", guess))
+            .collect::<Vec<_>>();
+        let share = render_share(Some(123), &rows, 6);
+        assert_eq!(share, "Wordle 123 3/6\n\n\u{2b1b}\u{1f7e8}\u{2b1b}\u{2b1b}\u{2b1b}\n\
+            \u{2b1b}\u{1f7e9}\u{2b1b}\u{2b1b}\u{1f7e9}\n\
+            \u{1f7e9}\u{1f7e9}\u{1f7e9}\u{1f7e9}\u{1f7e9}\n");
+
+        // Running out of guesses is a failure, and only the allowed guesses are shown.
+        let failed = std::iter::repeat_n(check_guess("robot", "moist"), 7).collect::<Vec<_>>();
+        let share = render_share(None, &failed, 6);
+        assert!(share.starts_with("Wordle X/6\n\n"));
+        assert_eq!(share.lines().count(), 2 + 6);
+        assert!(render_share(None, &rows[.. 2], 6).starts_with("Wordle X/6\n"));
+    }
+
+    #[test]
+    fn test_min_unique_letters() {
+        let words = ["eerie", "crane", "sassy", "toast", "pilot"];
+        let mut best = Strategy::Entropy.rank(&words, &Knowledge::new(5), &HashMap::new());
+        require_unique_letters(&mut best, 5);
+        best.sort();
+        assert_eq!(best, ["crane", "pilot"]);
+
+        // Without any words with that many letters, nothing is dropped.
+        let mut best = vec!["eerie", "sassy", "toast"];
+        require_unique_letters(&mut best, 5);
+        assert_eq!(best, ["eerie", "sassy", "toast"]);
+    }
+
+    #[test]
+    fn test_api_response() -> Result<(), SolveError> {
+        let response = r#"[
+            {"letter": "c", "state": "correct"},
+            {"letter": "R", "state": "present"},
+            {"letter": "a", "state": "absent"},
+            {"letter": "n", "state": "green"},
+            {"letter": "e", "state": "grey"}
+        ]"#;
+        assert_eq!(parse_api_response(response, 5)?, parse_input("*c?r!a*n!e", 5)?);
+        assert_eq!(parse_api_response(response, 6),
+            Err(SolveError::LengthMismatch { expected: 6, actual: 5 }));
+        assert!(matches!(parse_api_response(r#"[{"letter": "c", "state": "blue"}]"#, 1),
+            Err(SolveError::BadApiResponse(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_prompt() {
+        let best = ["slate", "crane", "trace"];
+        let out = format_quiet(42, &best);
+        assert_eq!(out, "42\nslate\ncrane\ntrace");
+        assert!(out.lines().all(|line| line.chars().all(|c| c.is_ascii_alphanumeric())));
+    }
+
+    #[test]
+    fn test_show_strategies() {
+        let words = ["crane", "slate", "trace", "crate", "react"];
+        let strategies = "freq,entropy".split(',')
+            .map(|s| s.parse::<Strategy>().unwrap())
+            .collect::<Vec<_>>();
+        let lines = compare_strategies(&strategies, &words, &Knowledge::new(5),
+            &letter_frequencies(words));
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("frequency: "));
+        assert!(lines[1].starts_with("entropy: "));
+    }
+
+    #[test]
+    fn test_infer_forced() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(5);
+        k.add_infos(&parse_input("?t!h!o!r!n", 5)?, false)?;
+        let candidates = ["taste", "tacit", "table"];
+        assert_eq!(k.infer_forced(&candidates), [(0, 't'), (1, 'a')]);
+        assert_eq!(k.letters_pattern(), "t a _ _ _");
+        // Already known, so not reported again.
+        assert_eq!(k.infer_forced(&candidates), []);
+        Ok(())
+    }
+
+    #[test]
+    fn test_guesses_file() -> io::Result<()> {
+        let dictionary = dictionary_of(&["crane", "sorts", "robot", "rotor", "motor", "thorn"]);
+        let letter_freq = letter_frequencies(&dictionary);
+        let guesses = guess_word("robot", dictionary.clone(), None, &letter_freq,
+            &Strategy::Frequency, false);
+        let mut file = vec![];
+        write_transcript("robot", &guesses, &mut file)?;
+        let lines = String::from_utf8(file.clone()).unwrap();
+        assert_eq!(lines.lines().count(), guesses.len());
+
+        let transcript = read_transcript(file.as_slice(), 5)?;
+        let expected = guesses.iter()
+            .map(|(guess, _)| check_guess("robot", guess))
+            .collect::<Vec<_>>();
+        assert_eq!(transcript, expected);
+
+        assert!(read_transcript("crane *c?r!a 3\n".as_bytes(), 5).is_err());
+        assert!(read_transcript("crane *s?r!a!n!e 3\n".as_bytes(), 5).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_positional_greens() {
+        let words = ["baste", "beast", "beats", "abets", "betas", "tabes"];
+        assert!(is_positional_endgame(&words));
+        assert!(!is_positional_endgame(&["baste", "crane"]));
+
+        let dictionary = words.iter().map(|w| w.to_string()).collect::<BTreeSet<_>>();
+        let letter_freq = letter_frequencies(words);
+        let total = |strategy: Strategy| words.iter()
+            .map(|w| guess_word(w, dictionary.clone(), None, &letter_freq, &strategy, false).len())
+            .sum::<usize>();
+        assert!(total(Strategy::Positional) < total(Strategy::Frequency),
+            "{} vs {}", total(Strategy::Positional), total(Strategy::Frequency));
+
+        // The adaptive strategy goes positional here, rather than by how many candidates are left.
+        assert_eq!(Strategy::ADAPTIVE.for_candidates(&words), Strategy::Positional);
+        assert_eq!(Strategy::ADAPTIVE.for_candidates(&["baste", "crane"]), Strategy::Lookahead);
+        assert_eq!(Strategy::Entropy.for_candidates(&words), Strategy::Entropy);
+        assert_eq!(total(Strategy::ADAPTIVE), total(Strategy::Positional));
+    }
+
+    #[test]
+    fn test_dump_frequencies() -> io::Result<()> {
+        let words = ["crane", "sorts", "robot"];
+        let letter_freq = letter_frequencies(words);
+
+        let mut tsv = vec![];
+        write_frequencies(&letter_freq, false, &mut tsv)?;
+        let tsv = String::from_utf8(tsv).unwrap();
+        let rows = tsv.lines()
+            .map(|line| {
+                let (letter, freq) = line.split_once('\t').unwrap();
+                (letter.chars().next().unwrap(), freq.parse::<f64>().unwrap())
+            })
+            .collect::<Vec<_>>();
+        let letters = rows.iter().map(|&(c, _)| c).collect::<String>();
+        assert_eq!(letters, "abcenorst");
+        assert!((rows.iter().map(|&(_, f)| f).sum::<f64>() - 1.).abs() < 1e-9);
+
+        let mut json = vec![];
+        write_frequencies(&letter_freq, true, &mut json)?;
+        let parsed: HashMap<char, f64> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(parsed, letter_freq);
+        Ok(())
+    }
+
+    #[test]
+    fn test_interactive_guess_check() -> Result<(), SolveError> {
+        let candidates = dictionary_of(&["robot", "motor", "rotor"]);
+        let k = Knowledge::new(5);
+        assert_eq!(check_feedback(&k, &check_guess("robot", "rotor"), &candidates), None);
+        let warning = check_feedback(&k, &parse_input("!r!o!t!o!r", 5)?, &candidates);
+        assert!(warning.is_some_and(|w| w.contains("3 candidates")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_letter_set() {
+        let mut set = LetterSet::new();
+        assert!(set.is_empty());
+        for c in ['z', 'a', '+', 'a', '7', '+'] {
+            set.insert(c);
+        }
+        assert!(set.contains('a') && set.contains('z') && set.contains('+'));
+        assert!(!set.contains('b') && !set.contains('8') && !set.contains('A'));
+        assert_eq!(set.iter().collect::<String>(), "az+7");
+    }
+
+    #[test]
+    fn test_filter_count_capped() -> Result<(), SolveError> {
+        let words = ["robot", "motor", "rotor", "sorts", "thorn", "crane"];
+        let mut k = Knowledge::new(5);
+        k.add_infos(&parse_input("!c?r!a!n!e", 5)?, false)?;
+        // "thorn" and "crane" are ruled out.
+        assert_eq!(k.filter_count_capped(words, 100), 4);
+        assert_eq!(k.filter_count_capped(words, 4), 4);
+        assert_eq!(k.filter_count_capped(words, 3), 3);
+        assert_eq!(k.filter_count_capped(words, 0), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_target_list() {
+        let dictionary =
+            dictionary_of(&["crane", "sorts", "robot", "rotor", "motor", "thorn", "sassy"]);
+        let letter_freq = letter_frequencies(&dictionary);
+        let targets = ["robot".to_owned(), "sassy".to_owned(), "zebra".to_owned()];
+        let results = check_all_words(&dictionary, None, targets.iter(), &[], &letter_freq,
+            &Strategy::Frequency, None, None, &mut io::sink()).unwrap();
+        let lens = targets.iter()
+            .map(|w| {
+                guess_word(w, dictionary.clone(), None, &letter_freq, &Strategy::Frequency, false)
+                    .len()
+            })
+            .collect::<Vec<_>>();
+        // "zebra" isn't in the dictionary, so can't be found.
+        let mean = (lens[0] + lens[1]) as f64 / 2.;
+        assert_eq!(accuracy_report(&results, 6), format!(
+            "historical accuracy: 2/3 solved within 6 guesses (66.7%), {:.3} guesses on average",
+            mean));
+    }
+
+    #[test]
+    fn test_probe() -> Result<(), SolveError> {
+        // Four candidates differing only in the first letter: guessing any one of them can only
+        // rule out itself, but "bumph" tells apart all of them.
+        let candidates = ["batch", "hatch", "match", "patch"];
+        let mut k = Knowledge::new(5);
+        k.add_infos(&parse_input("!l*a*t*c*h", 5)?, false)?;
+        assert!(candidates.iter().all(|w| k.check_word(w, false)));
+        let allowed = ["batch", "hatch", "match", "patch", "latch", "bumph", "crane"];
+        assert_eq!(best_probe(&allowed, &candidates), Some(("bumph".to_owned(), 1.)));
+        assert_eq!(best_probe(&allowed, &[] as &[&str]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_length_buckets() -> io::Result<()> {
+        let words = ["cat", "crane", "robot", "abbreviate", "dog", "sassy", "x"];
+        let buckets = LengthBuckets::new(words.iter().map(|w| w.to_string()));
+        assert_eq!(buckets.of_length(5), ["crane", "robot", "sassy"]);
+        assert_eq!(buckets.of_length(3), ["cat", "dog"]);
+        assert!(buckets.of_length(4).is_empty());
+        assert!((1 ..= 12).all(|n| buckets.of_length(n).iter().all(|w| w.len() == n)));
+
+        let (dictionary, _) = load_dictionary(words.join("\n").as_bytes(), None, 10)?;
+        assert_eq!(dictionary.words().iter().collect::<Vec<_>>(), ["abbreviate"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_no() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(5);
+        k.add_infos(&parse_input("*b!o!u!g!h", 5)?, false)?;
+        assert_eq!(k.check_word_explain("crane"),
+            Err(Rejection::NotGreen { idx: 0, letter: 'c', green: 'b' }));
+        assert_eq!(explain_word(&k, "crane"),
+            "crane: has 'c' at position 1, but 'b' is green there");
+        assert_eq!(explain_word(&k, "brand"), "brand: still a candidate");
+        assert_eq!(k.check_word_explain("bread"), Ok(()));
+        assert_eq!(k.check_word_explain("broad"), Err(Rejection::Excluded { idx: 2, letter: 'o' }));
+        assert_eq!(k.check_word_explain("brie"),
+            Err(Rejection::WrongLength { expected: 5, actual: 4 }));
+
+        k.add_infos(&parse_input("*b?r!i!n!k", 5)?, false)?;
+        assert_eq!(k.check_word_explain("bleat"),
+            Err(Rejection::Missing { letter: 'r', need: 1, have: 0 }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ansi_paste() -> Result<(), SolveError> {
+        let row = "\x1b[30;42m C \x1b[0m\x1b[30;43m R \x1b[0m\x1b[37;100m A \x1b[0m\
+            \x1b[1;37;100m N \x1b[0m\x1b[102mE\x1b[0m";
+        assert_eq!(parse_ansi(row, 5)?, parse_input("*c?r!a!n*e", 5)?);
+        assert_eq!(parse_ansi(row, 6), Err(SolveError::LengthMismatch { expected: 6, actual: 5 }));
+        assert_eq!(parse_ansi(row, 4), Err(SolveError::TooManyLetters));
+        assert_eq!(parse_ansi("\x1b[42mc\x1b[0mr", 2), Err(SolveError::Uncolored('r')));
+        Ok(())
+    }
+
+    #[test]
+    fn test_worst_case_for_opener() {
+        // After "batch", the other four words all get the same feedback, and each guess after that
+        // can only rule out itself, so the last of them takes five guesses.
+        let words = ["batch", "hatch", "latch", "match", "patch"];
+        assert_eq!(worst_case_for_opener("batch", &words, &Strategy::Frequency), 5);
+        // "bumph" splits up everything but "hatch" and "latch", so at worst it takes two more.
+        assert_eq!(worst_case_for_opener("bumph", &words, &Strategy::Frequency), 3);
+        assert_eq!(worst_case_for_opener("batch", &[] as &[&str], &Strategy::Frequency), 0);
+    }
+
+    #[test]
+    fn test_best_candidates_by_entropy() {
+        // "bumph" tells apart all but "hatch" and "latch"; each of the others only tells itself
+        // apart from the rest.
+        let words = ["batch", "hatch", "latch", "match", "patch", "bumph"];
+        let best = best_candidates_by_entropy(&words, &Knowledge::new(5), &words);
+        assert_eq!(best[0].0, "bumph");
+        assert_eq!(best.len(), 6);
+
+        let many = (0 .. 26).map(|i| format!("{}atch", (b'a' + i) as char)).collect::<Vec<_>>();
+        assert_eq!(best_candidates_by_entropy(&many, &Knowledge::new(5), &many).len(), 10);
+    }
+
+    #[test]
+    fn test_entropy_openers() -> io::Result<()> {
+        let (answers, _) = read_dictionary(BufReader::new(File::open("answers.txt")?), None)?;
+        let probes = ["crane", "slate", "fuzzy", "jazzy", "mamma", "vivid", "queue", "kayak"];
+        let ranked = best_candidates_by_entropy(&answers, &Knowledge::new(5), &probes);
+        let top = ranked.iter().take(2).map(|(w, _)| w.as_str()).collect::<BTreeSet<_>>();
+        assert_eq!(top, BTreeSet::from(["crane", "slate"]));
+        assert!(ranked[1].1 > ranked[2].1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_guess_strategy_trait() {
+        let words = ["crane", "eerie", "slate", "pilot", "toast"]
+            .iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        let letter_freq = letter_frequencies(&words);
+        let knowledge = Knowledge::new(5);
+        let strategies: Vec<Box<dyn GuessStrategy>> = vec![
+            Box::new(FrequencyStrategy::new(letter_freq.clone())),
+            Box::new(EntropyStrategy),
+        ];
+        for strategy in &strategies {
+            let ranked = strategy.rank(&words, &knowledge);
+            assert_eq!(ranked.len(), words.len());
+            assert!(ranked.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        }
+
+        // The free function gives the same order as the frequency strategy.
+        let ranked = strategies[0].rank(&words, &knowledge);
+        let best = best_candidates(words.iter(), &knowledge, &letter_freq);
+        assert_eq!(ranked.iter().map(|(w, _)| w).collect::<Vec<_>>(), best);
+        assert_eq!(ranked.last().unwrap().0, "eerie");
+
+        // Each strategy ranks with its GuessStrategy.
+        for strategy in [Strategy::Frequency, Strategy::Entropy, Strategy::Lookahead,
+            Strategy::Positional, Strategy::Discriminating, Strategy::ADAPTIVE]
+        {
+            let ranked = strategy.guess_strategy(&words, &letter_freq)
+                .rank(&words, &knowledge)
+                .into_iter()
+                .map(|(word, _)| word)
+                .collect::<Vec<_>>();
+            assert_eq!(strategy.rank(&words, &knowledge, &letter_freq), ranked, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn minimax_beats_frequency_on_hard_cases() {
+        let (dictionary, letter_freq) = fixture();
+        let frequency = FrequencyStrategy::new(letter_freq);
+        let worst_case = |guess: &str, candidates: &[String]| {
+            pattern_distribution(guess, candidates).into_values().max().unwrap()
+        };
+        let mut beaten = vec![];
+        for answer in FIXTURE_WORDS {
+            let mut knowledge = Knowledge::new(5);
+            knowledge.add_infos(&check_guess(answer, "slate"), false).unwrap();
+            let candidates = dictionary.iter()
+                .filter(|word| knowledge.check_word(word, false))
+                .cloned()
+                .collect::<Vec<_>>();
+            let by_frequency = &frequency.rank(&candidates, &knowledge)[0].0;
+            let by_minimax = MinimaxStrategy.rank(&candidates, &knowledge);
+            let (best, score) = &by_minimax[0];
+            assert_eq!(-score, worst_case(best, &candidates) as f64);
+            assert!(worst_case(best, &candidates) <= worst_case(by_frequency, &candidates));
+            if worst_case(best, &candidates) < worst_case(by_frequency, &candidates) {
+                beaten.push(*answer);
+            }
+        }
+        assert!(beaten.contains(&"robot"), "{:?}", beaten);
+
+        // Every candidate is returned, and those scored are ranked the same as by
+        // best_candidates_minimax.
+        let candidates = ["robot", "motor", "rotor", "tutor", "thorn"].map(str::to_owned);
+        let ranked = MinimaxStrategy.rank(&candidates, &Knowledge::new(5));
+        assert_eq!(ranked.len(), candidates.len());
+        let scored = ranked.iter()
+            .filter(|(_, score)| score.is_finite())
+            .map(|(word, score)| (word.clone(), -score as usize))
+            .collect::<Vec<_>>();
+        assert_eq!(scored, best_candidates_minimax(&candidates, &Knowledge::new(5)));
+
+        // Nothing beats telling them all apart, so the guesses after one which does aren't scored.
+        let words = ["robot", "motor", "crane"];
+        assert_eq!(rank_by_worst_case(&words, &words),
+            [("robot".to_owned(), Some(1)), ("motor".to_owned(), None), ("crane".to_owned(), None)]);
+        let ranked = MinimaxStrategy.rank(&["robot", "crane"].map(str::to_owned),
+            &Knowledge::new(5));
+        assert_eq!(ranked, [("robot".to_owned(), -1.), ("crane".to_owned(), f64::NEG_INFINITY)]);
+    }
+
+    #[test]
+    fn test_known_pattern() {
+        assert_eq!(parse_known_pattern("c_a_e", 5), Ok(vec![(0, 'c'), (2, 'a'), (4, 'e')]));
+        assert_eq!(parse_known_pattern("c_a_", 5),
+            Err(SolveError::LengthMismatch { expected: 5, actual: 4 }));
+        assert_eq!(parse_known_pattern("c_a?e", 5), Err(SolveError::UnprocessedInput('?')));
+
+        let mut knowledge = Knowledge::new(5);
+        for (idx, c) in parse_known_pattern("c_a_e", 5).unwrap() {
+            knowledge.require_at(idx, c).unwrap();
+        }
+        assert_eq!(knowledge.letters_pattern(), "c _ a _ e");
+        let words = ["crane", "chafe", "crate", "cease", "caste"];
+        let left = words.iter().filter(|w| knowledge.check_word(w, false)).collect::<Vec<_>>();
+        assert_eq!(left, [&"crane", &"chafe", &"crate", &"cease"]);
+
+        assert_eq!(knowledge.require_at(0, 'x'),
+            Err(KnowledgeError::ConflictingExact { position: 0, existing: 'c', new: 'x' }));
+        assert_eq!(knowledge.require_at(5, 'x'),
+            Err(KnowledgeError::PositionOutOfRange { position: 5, num_letters: 5 }));
+    }
+
+    #[test]
+    fn test_max_have() {
+        let mut knowledge = Knowledge::new(5);
+        let infos = check_guess("there", "eerie");
+        assert_eq!(format_infos(&infos), "?e!e?r!i*e");
+        knowledge.add_infos(&infos, false).unwrap();
+
+        assert!(knowledge.check_word("there", false));
+        // The gray e still rules e out of the second position.
+        assert_eq!(knowledge.check_word_explain("genre"),
+            Err(Rejection::Excluded { idx: 1, letter: 'e' }));
+        assert!(!knowledge.check_word("reeve", false));
+        // Not a word, but with three e's where they aren't ruled out.
+        assert_eq!(knowledge.check_word_explain("rteee"),
+            Err(Rejection::TooMany { letter: 'e', max: 2, have: 3 }));
+        assert!(!knowledge.check_word("eerie", false));
+    }
+
+    #[test]
+    fn test_present_not_at() -> Result<(), SolveError> {
+        let (c, positions) = parse_present_not_at("e:1,4").unwrap();
+        assert_eq!((c, positions.as_slice()), ('e', [0, 3].as_slice()));
+        assert_eq!(parse_present_not_at("e"), Ok(('e', vec![])));
+        assert!(parse_present_not_at("e:0").is_err());
+        assert!(parse_present_not_at("e:1,x").is_err());
+        assert!(parse_present_not_at("ex:1").is_err());
+
+        let mut k = Knowledge::new(5);
+        k.require_somewhere(c);
+        k.forbid_positions(c, &positions)?;
+        let words = ["eagle", "steer", "crane", "ocean", "stomp"];
+        let left = words.iter().filter(|w| k.check_word(w, false)).collect::<Vec<_>>();
+        // Unlike the "not" command, "e" has to be in there somewhere, so "stomp" is out.
+        assert_eq!(left, [&"crane", &"ocean"]);
+        assert_eq!(k.forbid_positions('e', &[5]),
+            Err(KnowledgeError::PositionOutOfRange { position: 5, num_letters: 5 }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_normal_mode() {
+        // Lots of words differing only in the first letter: guessing them one at a time is slow,
+        // but a word using several of those letters narrows them down quickly.
+        let dictionary = dictionary_of(&["batch", "catch", "hatch", "latch", "match", "patch",
+            "watch", "chomp", "blimp", "clamp", "plumb"]);
+        let letter_freq = letter_frequencies(&dictionary);
+        let answers = ["batch", "hatch", "latch", "match", "patch", "watch"];
+
+        let best = best_candidates_unrestricted(&dictionary.iter().collect::<Vec<_>>(), &answers,
+            &Knowledge::new(5), &letter_freq);
+        assert_eq!(best[0], "blimp");
+
+        let mut total = (0, 0);
+        for word in answers {
+            let normal = guess_word(word, dictionary.clone(), None, &letter_freq,
+                &Strategy::Frequency, true);
+            let hard = guess_word(word, dictionary.clone(), None, &letter_freq,
+                &Strategy::Frequency, false);
+            assert_eq!(normal[0].0, "blimp");
+            assert_eq!(normal.last().unwrap(), &(word.to_owned(), 1));
+            total.0 += normal.len();
+            total.1 += hard.len();
+        }
+        assert!(total.0 < total.1, "{:?}", total);
+
+        // With two or fewer answers, guess one of them.
+        let two = best_candidates_unrestricted(&dictionary.iter().collect::<Vec<_>>(),
+            &answers[.. 2], &Knowledge::new(5), &letter_freq);
+        assert_eq!(two, ["batch", "hatch"]);
+    }
+
+    #[test]
+    fn test_max_have_abbey() -> Result<(), SolveError> {
+        let mut k = Knowledge::new(5);
+        let infos = check_guess("abode", "abbey");
+        assert_eq!(format_infos(&infos), "*a*b!b?e!y");
+        k.add_infos(&infos, false)?;
+        // The gray "b" means there's only the one.
+        assert!(k.check_word("abode", false));
+        assert!(!k.check_word("abexb", false));
+
+        // A later guess with a yellow and a gray "e" pins that down too.
+        let infos = check_guess("abode", "ebbed");
+        assert_eq!(format_infos(&infos), "?e*b!b!e?d");
+        k.add_infos(&infos, false)?;
+        assert!(k.check_word("abode", false));
+        assert_eq!(k.check_word_explain("abede"),
+            Err(Rejection::TooMany { letter: 'e', max: 1, have: 2 }));
+        // Letters only ever seen gray on their own are just ruled out.
+        assert_eq!(k.check_word_explain("abyde"),
+            Err(Rejection::Excluded { idx: 2, letter: 'y' }));
+
+        // In the lenient mode, a gray tile doesn't say anything about the count.
+        let mut lenient = Knowledge::new(5).with_gray_means_absent(false);
+        lenient.add_infos(&check_guess("abode", "abbey"), false)?;
+        assert!(lenient.check_word("abexb", false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_guess_duplicates() {
+        use Info::*;
+        assert_eq!(check_guess("spree", "speed"),
+            [Exact('s'), Exact('p'), Somewhere('e'), Exact('e'), No('d')]);
+        assert_eq!(check_guess("aaccc", "aabbb"),
+            [Exact('a'), Exact('a'), No('b'), No('b'), No('b')]);
+        // Only as many copies as the word has get colored, left to right.
+        assert_eq!(check_guess("abode", "ebbed"),
+            [Somewhere('e'), Exact('b'), No('b'), No('e'), Somewhere('d')]);
+        // Greens take priority over yellows for the same letter, even when they come later.
+        assert_eq!(check_guess("there", "eerie"),
+            [Somewhere('e'), No('e'), Somewhere('r'), No('i'), Exact('e')]);
+        assert_eq!(check_guess("robot", "robot"), [Exact('r'), Exact('o'), Exact('b'), Exact('o'),
+            Exact('t')]);
+    }
+
+    #[test]
+    fn test_snapshot() -> io::Result<()> {
+        let (dictionary, letter_freq) = small_fixture();
+        let words = dictionary.iter().collect::<Vec<_>>();
+        let run = || {
+            let results = check_all_words(&dictionary, None, words.iter().copied(), &[],
+                &letter_freq, &Strategy::Frequency, None, None, &mut io::sink()).unwrap();
+            snapshot_counts(&words, &results)
+        };
+
+        let mut file = vec![];
+        write_snapshot(&run(), &mut file)?;
+        let old = read_snapshot(file.as_slice())?;
+        assert_eq!(old.len(), 6);
+        assert_eq!(compare_snapshots(&old, &run()), []);
+
+        let mut new = run();
+        new.insert("robot".to_owned(), None);
+        new.remove("thorn");
+        // Words missing from either side aren't compared.
+        assert_eq!(compare_snapshots(&old, &new), [("robot".to_owned(), old["robot"], None)]);
+        assert!(read_snapshot("robot three\n".as_bytes()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_report_json() -> io::Result<()> {
+        let (dictionary, letter_freq) = small_fixture();
+        let words = dictionary.iter().collect::<Vec<_>>();
+        let mut old = Snapshot::new();
+        old.insert("robot".to_owned(), None);
+        let report = CheckReport {
+            max_guesses: Some(6),
+            histogram_width: Some(40),
+            compare_to: Some(("old.txt", &old)),
+        };
+
+        for format in [OutputFormat::Text, OutputFormat::Json] {
+            let (mut out, mut log) = (vec![], vec![]);
+            let results = check_all_words(&dictionary, None, words.iter().copied(), &[],
+                &letter_freq, &Strategy::Frequency, None, Some(format), &mut out)?;
+            report.write(&words, &results, format, &mut out, &mut log)?;
+            let (out, log) = (String::from_utf8(out).unwrap(), String::from_utf8(log).unwrap());
+            let report = if format == OutputFormat::Text {
+                assert_eq!(log, "");
+                out
+            } else {
+                // Only the results go to stdout, so it's all JSON.
+                let json = serde_json::from_str::<serde_json::Value>(&out).unwrap();
+                assert_eq!(json.as_array().map(Vec::len), Some(6), "{}", out);
+                log
+            };
+            assert!(report.contains("6 words: "), "{}", report);
+            assert!(report.contains("5 words weren't in old.txt"), "{}", report);
+            assert!(report.contains("changed: robot X -> "), "{}", report);
+        }
+        Ok(())
+    }
 
     #[test]
-    fn test_5() -> Result<(), String> {
-        use Info::*;
+    fn test_answers_list() {
+        let answers = dictionary_of(&["batch", "hatch", "latch", "match", "patch", "watch"]);
+        let mut dictionary = dictionary_of(&["chomp", "blimp", "clamp", "plumb", "thumb", "lymph"]);
+        dictionary.extend(answers.iter().cloned());
+        let letter_freq = letter_frequencies(&dictionary);
+
+        for strategy in [Strategy::Frequency, Strategy::Entropy] {
+            let results = check_all_words(&dictionary, Some(&answers), answers.iter(), &[],
+                &letter_freq, &strategy, None, None, &mut io::sink()).unwrap();
+            assert_eq!(results.len(), answers.len());
+            for (word, guesses) in answers.iter().zip(&results) {
+                assert_eq!(guesses.last().unwrap(), &(word.clone(), 1), "{:?}", strategy);
+                // Only answers are counted as candidates.
+                assert!(guesses.iter().all(|(_, remaining)| *remaining <= answers.len()));
+            }
+        }
+
+        // Entropy finds a better opener among the non-answers.
+        let guesses = guess_word("watch", answers.clone(), Some(&dictionary), &letter_freq,
+            &Strategy::Entropy, false);
+        assert!(!answers.contains(&guesses[0].0), "{:?}", guesses);
+
+        // Down to two candidates, one of them is guessed.
+        let two = ["batch", "hatch"];
+        let best = Strategy::Entropy.rank_guesses(&dictionary.iter().collect::<Vec<_>>(), &two,
+            &Knowledge::new(5), &letter_freq);
+        assert!(two.contains(&best[0].as_str()));
+    }
+
+    #[test]
+    fn test_short_rows() -> Result<(), SolveError> {
+        assert_eq!(parse_input("*c?r!a", 5),
+            Err(SolveError::LengthMismatch { expected: 5, actual: 3 }));
+        assert_eq!(parse_input("", 5), Err(SolveError::LengthMismatch { expected: 5, actual: 0 }));
+
+        let partial = parse_input_partial("*c?r!a", 5)?;
+        assert_eq!(partial.len(), 3);
         let mut k = Knowledge::new(5);
-        k.add_infos(&[
-            No('a'),
-            No('d'),
-            No('i'),
-            No('e'),
-            No('u'),
-        ], true)?;
-        assert!(k.check_word("thorn", true));
-        k.add_infos(&[
-            Somewhere('t'),
-            No('h'),
-            Somewhere('o'),
-            Somewhere('r'),
-            No('n'),
-        ], true)?;
-        assert!(k.check_word("sorts", true));
-        k.add_infos(&[
-            No('s'),
-            Exact('o'),
-            Somewhere('r'),
-            Somewhere('t'),
-            No('s'),
-        ], true)?;
-        assert!(!k.check_word("palmy", true));
-        k.add_infos(&[
-            No('p'),
-            No('a'),
-            No('l'),
-            No('m'),
-            No('y'),
-        ], true)?;
-        eprintln!("{:#?}", k);
-        assert!(k.check_word("robot", true));
-        assert!(!k.check_word("motor", true));
+        k.add_infos(&partial, false)?;
+        assert!(k.check_word("cover", false));
+        assert_eq!(parse_input_partial("*c?r!a!n!e!s", 5), Err(SolveError::TooManyLetters));
         Ok(())
     }
 
     #[test]
-    fn test_11_1() -> Result<(), String> {
-        use Info::*;
-        let mut k = Knowledge::new(11);
-        // !u?l*c?e?r?a!t!i*o!n!s
-        k.add_infos(&[
-            No('u'),
-            Somewhere('l'),
-            Exact('c'),
-            Somewhere('e'),
-            Somewhere('r'),
-            Somewhere('a'),
-            No('t'),
-            No('i'),
-            Exact('o'),
-            No('n'),
-            No('s'),
-        ], true)?;
-        assert!(k.check_word("archaeology", true));
+    fn test_check_guess_double_letters() {
+        for (word, guess, expected) in [
+            ("llama", "llano", "*l*l*a!n!o"),
+            ("llama", "alloy", "?a*l?l!o!y"),
+            ("robot", "otter", "?o?t!t!e?r"),
+            ("crepe", "eerie", "?e!e?r!i*e"),
+            ("apple", "paper", "?p?a*p?e!r"),
+            ("sassy", "asses", "?a?s*s!e?s"),
+            ("geese", "eerie", "?e*e!r!i*e"),
+            ("abbey", "babes", "?b?a*b*e!s"),
+            ("lolly", "loyal", "*l*o?y!a?l"),
+        ] {
+            assert_eq!(format_infos(&check_guess(word, guess)), expected, "{} on {}", guess, word);
+        }
+    }
+
+    #[test]
+    fn test_solver_game() -> Result<(), SolveError> {
+        let words = ["batch", "catch", "hatch", "latch", "match", "patch", "crane", "hatch"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        let mut solver = Solver::new(words, Box::new(EntropyStrategy));
+        assert_eq!(solver.candidates().len(), 7);
+        assert_eq!(solver.num_guesses(), 0);
+        assert!(!solver.is_solved());
+
+        // Play the hard way, one "-atch" word at a time.
+        for (turn, guess) in ["batch", "catch", "hatch", "latch", "patch"].into_iter().enumerate() {
+            assert!(solver.suggest().iter().all(|w| solver.candidates().contains(w)));
+            let result = check_guess("patch", guess);
+            solver.apply_result(guess, &result)?;
+            assert_eq!(solver.num_guesses(), turn + 1);
+            assert!(solver.candidates().iter().any(|w| w == "patch"));
+        }
+        assert!(solver.is_solved());
+        assert_eq!(solver.candidates(), ["patch"]);
+        assert_eq!(solver.history()[0], ("batch".to_owned(), check_guess("patch", "batch")));
+
+        // Bad feedback doesn't change anything.
+        assert_eq!(solver.apply_result("crane", &parse_input_partial("!c!r", 5)?),
+            Err(SolveError::LengthMismatch { expected: 5, actual: 2 }));
+        assert_eq!(solver.num_guesses(), 5);
         Ok(())
     }
 
     #[test]
-    fn test_parse() {
-        use Info::*;
-        assert_eq!(parse_input("!u?l*c?e?r?a!t!i*o!n!s", 11),
-            Ok(vec![
-                No('u'),
-                Somewhere('l'),
-                Exact('c'),
-                Somewhere('e'),
-                Somewhere('r'),
-                Somewhere('a'),
-                No('t'),
-                No('i'),
-                Exact('o'),
-                No('n'),
-                No('s'),
-            ]));
+    fn test_count_change() {
+        let dictionary = SMALL_FIXTURE_WORDS;
+        let mut k = Knowledge::new(5);
+        let mut previous = dictionary.len();
+        let mut shown = vec![];
+        for guess in ["thorn", "motor"] {
+            k.add_infos(&check_guess("rotor", guess), false).unwrap();
+            let count = dictionary.iter().filter(|w| k.check_word(w, false)).count();
+            shown.push(format_count_change(previous, count));
+            previous = count;
+        }
+        assert_eq!(shown, ["6 \u{2192} 4 (-2)", "4 \u{2192} 1 (-3)"]);
+        assert_eq!(format_count_change(48, 48), "48");
+        // Reloading the dictionary can bring some back.
+        assert_eq!(format_count_change(10, 12), "10 \u{2192} 12 (+2)");
     }
 
     #[test]
-    fn test_11_2() -> Result<(), String> {
-        let mut k = Knowledge::new(11);
-        k.add_infos(&parse_input("?u!l*c!e?r!a!t?i*o?n*s", 11)?, true)?;
-        assert!(k.check_word("incongruous", true));
+    fn test_fixed_openers() {
+        let (mut dictionary, _) = small_fixture();
+        dictionary.extend(dictionary_of(&["crane", "solid"]));
+        let letter_freq = letter_frequencies(&dictionary);
+        let openers = ["crane".to_owned(), "solid".to_owned()];
+        for word in ["robot", "tutor", "thorn"] {
+            let guesses = guess_word_with_openers(word, &openers, dictionary.clone(), None,
+                &letter_freq, &Strategy::Frequency, false, None);
+            let words = guesses.iter().map(|(guess, _)| guess.as_str()).collect::<Vec<_>>();
+            assert_eq!(words[..2], ["crane", "solid"]);
+            assert_eq!(words.last(), Some(&word));
+        }
+        // Finding the word with an opener ends the game early.
+        let guesses = guess_word_with_openers("crane", &openers, dictionary.clone(), None,
+            &letter_freq, &Strategy::Frequency, false, None);
+        assert_eq!(guesses, [("crane".to_owned(), 1)]);
+    }
+
+    #[test]
+    fn test_solver_hard_mode() -> Result<(), SolveError> {
+        let words = ["crane", "crate", "trace", "react", "slate"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        let mut solver = Solver::new(words, Box::new(EntropyStrategy)).with_hard_mode(true);
+        solver.apply_result("crane", &check_guess("trace", "crane"))?;
+        assert!(solver.suggest().iter().all(|w| solver.candidates().contains(w)));
+
+        // "slate" doesn't use the green r.
+        assert_eq!(solver.apply_result("slate", &check_guess("trace", "slate")),
+            Err(SolveError::HardModeViolation {
+                guess: "slate".to_owned(),
+                violated_constraint: Rejection::NotGreen { idx: 1, letter: 'l', green: 'r' },
+            }));
+        assert_eq!(solver.num_guesses(), 1);
+
+        solver.apply_result("trace", &check_guess("trace", "trace"))?;
+        assert!(solver.is_solved());
         Ok(())
     }
 
     #[test]
-    fn test_11_3() -> Result<(), String> {
-        let mut k = Knowledge::new(11);
-        // symptomatic / masochistic
-        k.add_infos(&parse_input("!u!l?c!e!r?a?t?i?o!n?s", 11)?, true)?;
-        assert!(k.check_word("symptomatic", true));
-        assert!(k.check_word("masochistic", true));
-        k.add_infos(&parse_input("?s!y?m!p!t?o!m?a*t*i*c", 11)?, true)?;
-        assert!(!k.check_word("symptomatic", true));
-        assert!(k.check_word("masochistic", true));
+    fn test_parse_emoji_guess() {
+        let infos = check_guess("trace", "crane");
+        let grid = render_emoji_grid(std::slice::from_ref(&infos));
+        assert_eq!(parse_emoji_guess("crane", &grid), Ok(infos));
+        assert_eq!(parse_emoji_guess("crane", "\u{2b1c}\u{1f7e9}\u{1f7e9}\u{2b1c}\u{1f7e8}"),
+            parse_input("!c*r*a!n?e", 5));
+        assert_eq!(parse_emoji_guess("crane", "\u{1f7e9}\u{1f7e9}"),
+            Err(SolveError::LengthMismatch { expected: 5, actual: 2 }));
+        assert_eq!(parse_emoji_guess("crane", "\u{1f7e9}\u{1f7e9}\u{1f7e6}\u{1f7e9}\u{1f7e9}"),
+            Err(SolveError::UnknownTile('\u{1f7e6}')));
+    }
+
+    #[test]
+    fn test_apply_guess() {
+        let mut by_pattern = Knowledge::new(5);
+        by_pattern.apply_guess("crane",
+            &[Pattern::Gray, Pattern::Green, Pattern::Yellow, Pattern::Gray, Pattern::Yellow])
+            .unwrap();
+        let mut by_infos = Knowledge::new(5);
+        by_infos.add_infos(&check_guess("bread", "crane"), false).unwrap();
+        let words = ["rearm", "react", "bread", "wreak", "freak", "great"];
+        assert_eq!(words.map(|w| by_pattern.check_word(w, false)),
+            [false, false, true, true, true, true]);
+        assert_eq!(words.map(|w| by_pattern.check_word(w, false)),
+            words.map(|w| by_infos.check_word(w, false)));
+        assert!(('a'..='z').all(|c| by_pattern.key_status(c) == by_infos.key_status(c)));
+
+        assert_eq!(by_pattern.apply_guess("crane", &[Pattern::Gray; 4]),
+            Err(KnowledgeError::LengthMismatch { expected: 5, actual: 4 }));
+    }
+
+    #[test]
+    fn test_pattern_string_roundtrip() {
+        // Grays rule a letter out everywhere, so they can come back in a different order.
+        let same = |a: &Knowledge, b: &Knowledge| {
+            FIXTURE_WORDS.iter().all(|w| a.check_word(w, false) == b.check_word(w, false))
+                && ('a'..='z').all(|c| a.key_status(c) == b.key_status(c))
+        };
+        for (answer, guess) in [("robot", "crane"), ("bread", "crane"), ("sissy", "speed"),
+            ("geese", "eerie"), ("crane", "crane"), ("lymph", "kayak")]
+        {
+            let row = format_infos(&check_guess(answer, guess));
+            let knowledge = Knowledge::from_pattern_string(&row, 5).unwrap();
+            let saved = knowledge.to_pattern_string().unwrap();
+            let resumed = Knowledge::from_pattern_string(&saved, 5).unwrap();
+            assert!(same(&knowledge, &resumed), "{} saved as {}", row, saved);
+            assert_eq!(resumed.to_pattern_string(), Some(saved));
+        }
+        assert_eq!(Knowledge::from_pattern_string("*r?o!b?o!t", 5).unwrap().to_pattern_string()
+            .as_deref(), Some("*r?o!b?o!t"));
+
+        // A second guess usually can't be written as one row, and with no guesses, there's
+        // nothing to write for each position.
+        let mut knowledge = Knowledge::new(5);
+        knowledge.add_infos(&check_guess("robot", "crane"), false).unwrap();
+        knowledge.add_infos(&check_guess("robot", "spurt"), false).unwrap();
+        assert_eq!(knowledge.to_pattern_string(), None);
+        assert_eq!(Knowledge::new(5).to_pattern_string(), None);
+
+        assert_eq!(Knowledge::from_pattern_string("*r*o*b", 5).err(),
+            Some(SolveError::LengthMismatch { expected: 5, actual: 3 }));
+    }
+
+    #[test]
+    fn test_adversarial_feedback() {
+        let candidates = dictionary_of(&["light", "might", "night", "sight", "tight", "robot"]);
+        let (infos, remaining) = adversarial_feedback("crane", &candidates);
+        assert_eq!(format_infos(&infos), "!c!r!a!n!e");
+        assert_eq!(remaining, dictionary_of(&["light", "might", "sight", "tight"]));
+
+        // Each of these has a bucket to itself; all gray comes first.
+        let (infos, remaining) = adversarial_feedback("lymph", &dictionary_of(&["light", "robot"]));
+        assert_eq!(format_infos(&infos), "!l!y!m!p!h");
+        assert_eq!(remaining, dictionary_of(&["robot"]));
+
+        // Too many letters for a pattern to fit in a small number.
+        let long = dictionary_of(&["abbreviated", "abbreviates", "authorities", "backgrounds"]);
+        let (infos, remaining) = adversarial_feedback("abbreviator", &long);
+        assert_eq!(format_infos(&infos), "*a*b*b*r*e*v*i*a*t!o!r");
+        assert_eq!(remaining, dictionary_of(&["abbreviated", "abbreviates"]));
+        let (infos, remaining) = adversarial_feedback("qqqqqqqqqqd", &long);
+        assert!(infos.iter().all(|info| matches!(info, Info::No(_))));
+        assert_eq!(remaining, dictionary_of(&["abbreviates", "authorities"]));
+
+        let (dictionary, letter_freq) = fixture();
+        let guesses = guess_adversarial(dictionary.clone(), &[], &letter_freq,
+            &Strategy::Frequency, None);
+        let answer = &guesses.last().unwrap().0;
+        assert!(dictionary.contains(answer));
+        assert_eq!(guesses.last().unwrap().1, 1);
+        // Every feedback the host gave fits the word it ended up with, so the solver goes the same
+        // way it would have if that had been the answer all along.
+        assert_eq!(guesses, guess_word(answer, dictionary.clone(), None, &letter_freq,
+            &Strategy::Frequency, false));
+
+        let capped = guess_adversarial(dictionary, &[], &letter_freq, &Strategy::Frequency,
+            Some(1));
+        assert_eq!(capped[1], (String::new(), 0));
+    }
+
+    #[test]
+    fn test_pattern_table() {
+        let guesses = ["crane", "robot", "speed", "erase", "geese"];
+        let answers = ["robot", "motor", "rotor", "eerie", "sorts", "thorn", "crane"];
+        let table = PatternTable::new(&guesses, &answers).unwrap();
+        for (g, guess) in guesses.iter().enumerate() {
+            for (a, answer) in answers.iter().enumerate() {
+                let infos = check_guess(answer, guess);
+                assert_eq!(table.get(g, a), encode_pattern(&infos));
+                assert_eq!(decode_pattern(table.get(g, a), guess), infos);
+            }
+        }
+        assert_eq!(encode_pattern(&parse_input("*c?r!a!n!e", 5).unwrap()), 2 * 81 + 27);
+
+        let remaining = [0, 1, 2, 5];
+        let subset = remaining.map(|i| answers[i]);
+        for metric in [SplitMetric::Entropy, SplitMetric::WorstCase] {
+            assert_eq!(table.rank(&remaining, metric), rank_by_split(&guesses, &subset, metric));
+        }
+        // The same goes for ranking by the words, as long as the table has them all.
+        let probes = ["geese", "robot", "crane"];
+        for metric in [SplitMetric::Entropy, SplitMetric::WorstCase] {
+            assert_eq!(table.rank_words(&probes, &subset, metric),
+                Some(rank_by_split(&probes, &subset, metric)));
+        }
+        assert_eq!(table.rank_words(&["sorts"], &subset, SplitMetric::Entropy), None);
+
+        // 3^11 patterns don't fit, but splitting longer words still works without the table.
+        let ten = "abbreviate".chars().map(Info::Exact).collect::<Vec<_>>();
+        assert_eq!(encode_pattern(&ten), 59048);
+        assert_eq!(PatternTable::new(&["abbreviated"], &["abbreviates"]).err(),
+            Some(SolveError::PatternTooLong { num_letters: 11 }));
+        let long = ["abbreviated", "abbreviates", "abbreviator"];
+        assert_eq!(rank_by_split(&["abbreviated"], &long, SplitMetric::WorstCase),
+            [("abbreviated".to_owned(), -1.)]);
+
+        // Solving lots of words by entropy goes through a table, with the same results.
+        let (dictionary, letter_freq) = fixture();
+        let words = dictionary.iter().collect::<Vec<_>>();
+        let results = solve_words_parallel(&words, &dictionary, None, &[], &letter_freq,
+            &Strategy::Entropy, None);
+        for result in &results {
+            assert_eq!(result.guesses, guess_word_with_openers(&result.word, &[],
+                dictionary.clone(), None, &letter_freq, &Strategy::Entropy, false, None));
+        }
+    }
+
+    #[test]
+    fn test_lookahead2() {
+        let dictionary = ["madly", "newly", "girly", "folly", "manly", "oddly"];
+        let knowledge = Knowledge::new(5);
+        let greedy = &rank_by_split(&dictionary, &dictionary, SplitMetric::ExpectedRemaining)[0].0;
+        assert_eq!(greedy, "madly");
+        let ranked = best_candidates_lookahead2(&dictionary, &knowledge);
+        assert_eq!(ranked.len(), dictionary.len());
+        assert_eq!(ranked[0].0, "manly");
+        let greedy_remaining = ranked.iter().find(|(word, _)| word == greedy).unwrap().1;
+        assert!(ranked[0].1 < greedy_remaining);
+        assert!(ranked.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn test_answer_for_day() {
+        let answers = ["cigar", "rebut", "sissy", "humph", "awake"];
+        assert_eq!(answer_for_day(&answers, 0), Some("cigar"));
+        assert_eq!(answer_for_day(&answers, 3), Some("humph"));
+        assert_eq!(answer_for_day(&answers, 4), Some("awake"));
+        // Past the end of the list, the answer isn't known.
+        assert_eq!(answer_for_day(&answers, 5), None);
+        assert_eq!(answer_for_day(&answers, 6), None);
+        assert_eq!(answer_for_day::<&str>(&[], 3), None);
+    }
+
+    #[test]
+    fn test_max_guesses() {
+        let dictionary =
+            dictionary_of(&["batch", "catch", "hatch", "latch", "match", "patch", "watch"]);
+        let letter_freq = letter_frequencies(&dictionary);
+        let unlimited = guess_word_with_openers("watch", &[], dictionary.clone(), None,
+            &letter_freq, &Strategy::Frequency, false, None);
+        assert!(unlimited.len() > 3);
+        let limited = guess_word_with_openers("watch", &[], dictionary.clone(), None,
+            &letter_freq, &Strategy::Frequency, false, Some(3));
+        assert_eq!(limited[..3], unlimited[..3]);
+        assert_eq!(limited[3], (String::new(), 0));
+        assert_eq!(limited.len(), 4);
+        let enough = guess_word_with_openers("watch", &[], dictionary.clone(), None,
+            &letter_freq, &Strategy::Frequency, false, Some(unlimited.len()));
+        assert_eq!(enough, unlimited);
+
+        let words = dictionary.iter().collect::<Vec<_>>();
+        let results = check_all_words(&dictionary, None, words.iter().copied(), &[],
+            &letter_freq, &Strategy::Frequency, Some(3), None, &mut io::sink()).unwrap();
+        let failed = results.iter()
+            .filter(|guesses| guesses.last().unwrap().0.is_empty())
+            .count();
+        assert!(failed > 0);
+        assert!(results.iter().all(|guesses| guesses.len() <= 4));
+        let report = failure_report(&words, &results, 3);
+        let mut lines = report.lines();
+        assert_eq!(lines.next().unwrap(),
+            format!("{} words solved in \u{2264}3 guesses; {} words failed", 7 - failed, failed));
+        assert!(lines.next().unwrap().starts_with("failed: "));
+    }
+
+    #[test]
+    fn test_position_freq() -> io::Result<()> {
+        let (answers, _) = read_dictionary(BufReader::new(File::open("answers.txt")?), None)?;
+        let position_freq = build_position_freq(&answers);
+        assert_eq!(position_freq.len(), 5);
+        for freq in &position_freq {
+            assert!((freq.values().sum::<f64>() - 1.).abs() < 1e-9);
+        }
+        // Lots of answers end in Y, but hardly any start with it.
+        assert!(position_freq[4][&'y'] > 10. * position_freq[0][&'y']);
+
+        let knowledge = Knowledge::new(5);
+        let global = best_candidates(answers.iter(), &knowledge, &letter_frequencies(&answers));
+        let by_position = best_candidates_with_position_freq(answers.iter(), &knowledge,
+            &position_freq);
+        assert_eq!(by_position.len(), 10);
+        assert!(by_position.iter().all(|w| unique_letters(w) == 5));
+        assert_ne!(global[..10], by_position[..]);
         Ok(())
     }
 
     #[test]
-    fn test_11_4() -> Result<(), String> {
-        let mut k = Knowledge::new(11);
-        // symptomatic / masochistic
-        k.add_infos(&parse_input("!u!l?c!e!r?a?t?i?o!n?s", 11)?, true)?;
-        assert!(k.check_word("symptomatic", true));
-        assert!(k.check_word("masochistic", true));
-        k.add_infos(&parse_input("?m?a?s?o!c!h!i!s*t*i*c", 11)?, true)?;
-        assert!(k.check_word("symptomatic", true));
-        assert!(!k.check_word("masochistic", true));
+    fn test_positional_anagrams() {
+        // Global frequency can't tell anagrams apart, but most of these words start with S.
+        let words = ["least", "slate", "stale", "steal", "tales", "sight", "stomp", "spine"];
+        let dictionary = dictionary_of(&words);
+        let position_freq = build_position_freq(&dictionary);
+        for freq in &position_freq {
+            assert!((freq.values().sum::<f64>() - 1.).abs() < 1e-9);
+        }
+        assert_eq!(position_freq[0][&'s'], 6. / 8.);
+
+        let anagrams = ["least", "slate", "stale", "steal", "tales"];
+        let knowledge = Knowledge::new(5);
+        let global = best_candidates(anagrams.iter().copied(), &knowledge,
+            &letter_frequencies(&dictionary));
+        assert_eq!(global, anagrams);
+        let by_position = best_candidates_with_position_freq(anagrams.iter().copied(),
+            &knowledge, &position_freq);
+        assert!(by_position[0].starts_with('s'), "{:?}", by_position);
+        assert!(by_position[3..].iter().all(|w| !w.starts_with('s')), "{:?}", by_position);
+
+        // The positions multiply, so one letter never seen where it is sinks a word, however
+        // common its other letters are there.
+        let by_position = best_candidates_with_position_freq(["stale", "etals"].iter().copied(),
+            &knowledge, &position_freq);
+        assert_eq!(by_position, ["stale", "etals"]);
+    }
+
+    #[test]
+    fn test_scoring_mode_batch() -> Result<(), structopt::clap::Error> {
+        let args = |extra: &[&str]| {
+            Args::from_iter_safe(["wordle-solve"].iter().chain(extra).chain(&["5", "words.txt"]))
+        };
+        assert_eq!(batch_mode(&args(&["--positional"])?), None);
+        assert_eq!(batch_mode(&args(&["--positional", "--word", "robot"])?), Some("--word"));
+        assert_eq!(batch_mode(&args(&["--check-all-words"])?), Some("--check-all-words"));
+        assert_eq!(batch_mode(&args(&["--coverage", "crane"])?), Some("--coverage"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_board() -> io::Result<()> {
+        let (dictionary, letter_freq) = fixture();
+        let secrets = ["robot", "watch", "night", "solid"];
+        let mut boards = MultiBoard::new(secrets.len(), &Knowledge::new(5), &dictionary);
+        // Play it through, writing down the feedback that would be typed in.
+        let mut input = String::new();
+        let mut guesses = 0;
+        while !boards.all_solved() {
+            let guess = boards.best_guess(&dictionary, &letter_freq).unwrap();
+            guesses += 1;
+            for (board, secret) in secrets.iter().enumerate() {
+                if boards.is_solved(board) {
+                    continue;
+                }
+                let infos = check_guess(secret, &guess);
+                input += &format_infos(&infos);
+                input += "\n";
+                boards.apply(board, &infos).unwrap();
+            }
+            assert!(guesses <= 9, "{:?}", boards);
+        }
+        assert!(boards.best_guess(&dictionary, &letter_freq).is_none());
+
+        // Once a board is solved, only the others count.
+        let mut boards = MultiBoard::new(2, &Knowledge::new(5), &dictionary);
+        boards.apply(0, &check_guess("robot", "robot")).unwrap();
+        boards.apply(1, &check_guess("watch", "batch")).unwrap();
+        assert!(boards.is_solved(0) && !boards.is_solved(1));
+        let alone = MultiBoard::new(1, &Knowledge::new(5), &boards.boards[1].1);
+        assert_eq!(boards.best_guess(&dictionary, &letter_freq),
+            alone.best_guess(&dictionary, &letter_freq));
+
+        let mut boards = MultiBoard::new(secrets.len(), &Knowledge::new(5), &dictionary);
+        let mut out = vec![];
+        play_boards(&mut boards, &dictionary, &letter_freq, 5,
+            &mut format!("*r*o*b\n{}", input).as_bytes(), &mut out)?;
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Bad input"), "{}", out);
+        assert!(out.ends_with(&format!("All 4 boards solved in {} guesses.\n", guesses)), "{}", out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_discriminating() {
+        let (dictionary, letter_freq) = fixture();
+        let mut knowledge = Knowledge::new(5);
+        knowledge.add_infos(&check_guess("crane", "lymph"), false).unwrap();
+        let words = dictionary.iter()
+            .filter(|w| knowledge.check_word(w, false))
+            .collect::<Vec<_>>();
+        assert_eq!(words, ["crane", "queue", "robot", "rotor", "sorts", "tutor"]);
+
+        // Frequency likes the common letters in "crane", but it gets the same feedback for four of
+        // the others; "tutor" tells them all apart.
+        assert_eq!(Strategy::Frequency.rank(&words, &knowledge, &letter_freq)[0], "crane");
+        let ranked = rank_by_discrimination(&words, &words, &knowledge, &letter_freq);
+        assert_eq!(ranked[0], ("tutor".to_owned(), 1));
+        assert_eq!(ranked.last().unwrap(), &("crane".to_owned(), 4));
+        assert_eq!(Strategy::Discriminating.rank(&words, &knowledge, &letter_freq)[0], "tutor");
+        assert_eq!("discriminating".parse(), Ok(Strategy::Discriminating));
+    }
+
+    #[test]
+    fn test_minimax() {
+        let dictionary = ["batch", "catch", "hatch", "latch", "match", "patch", "chalk", "cloth"];
+        let knowledge = Knowledge::new(5);
+        let ranked = best_candidates_minimax(&dictionary, &knowledge);
+        let worst_case = |guess: &str| {
+            *pattern_distribution(guess, dictionary).values().max().unwrap()
+        };
+        let smallest = dictionary.iter().map(|w| worst_case(w)).min().unwrap();
+        assert_eq!(ranked[0].1, smallest);
+        assert_eq!(worst_case(&ranked[0].0), smallest);
+        assert!(ranked.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        // Guessing one of the "-atch" words leaves the other five together at worst.
+        assert_eq!(ranked.iter().find(|(w, _)| w == "match").unwrap().1, 5);
+    }
+
+    #[test]
+    fn test_emoji_input() {
+        // Some platforms put a variation selector after each square.
+        let tiles = "\u{1f7e9}\u{2b1b}\u{fe0f}\u{1f7e8} \u{2b1c}\u{fe0f}\u{1f7e9}\n";
+        assert_eq!(parse_emoji_input_with_letters("crane", tiles, 5),
+            parse_input("*c!r?a!n*e", 5));
+        assert_eq!(parse_emoji_input_with_letters("cranes", tiles, 5),
+            Err(SolveError::LengthMismatch { expected: 5, actual: 6 }));
+    }
+
+    #[test]
+    fn test_opener_coverage() -> io::Result<()> {
+        let (dictionary, _) = read_dictionary(BufReader::new(File::open("answers.txt")?), None)?;
+        let letter_freq = letter_frequencies(&dictionary);
+        let report = opener_coverage(&["crane", "solid"], &letter_freq, 10);
+        assert_eq!(report.missed, ['t']);
+        assert_eq!(report.covered.len(), 9);
+        assert_eq!(report.missed_vowels, ['u']);
+        assert_eq!(report.to_string().lines().nth(1), Some("misses: t"));
+
+        let report = opener_coverage(&["crane"], &letter_freq, 10);
+        assert_eq!(report.covered, ['e', 'a', 'r', 'n', 'c']);
+        assert_eq!(report.missed_vowels, ['i', 'o', 'u']);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_guess_colored() {
+        let infos = check_guess("trace", "crane");
+        assert_eq!(format_guess_colored("crane", &infos),
+            "\x1b[30;43m c \x1b[0m\x1b[30;42m r \x1b[0m\x1b[30;42m a \x1b[0m\x1b[37;100m n \x1b[0m\
+            \x1b[30;42m e \x1b[0m");
+        // Pasting it back in gets the same feedback.
+        assert_eq!(parse_ansi(&format_row(&infos, true), 5), Ok(infos.clone()));
+        assert_eq!(format_row(&infos, false), "?c*r*a!n*e");
+    }
+
+    #[test]
+    fn test_color_mode() -> Result<(), SolveError> {
+        let infos = check_guess("trace", "crane");
+        assert_eq!(render_infos_colored(&infos), format_guess_colored("crane", &infos));
+
+        let mut knowledge = Knowledge::new(5);
+        knowledge.add_infos(&infos, false)?;
+        assert_eq!(highlight_known_greens("trace", &knowledge),
+            "t\x1b[30;42mr\x1b[0m\x1b[30;42ma\x1b[0mc\x1b[30;42me\x1b[0m");
+        assert_eq!(highlight_known_greens("fuzzy", &Knowledge::new(5)), "fuzzy");
+
+        assert_eq!("always".parse(), Ok(ColorMode::Always));
+        assert!("sometimes".parse::<ColorMode>().is_err());
+        assert!(use_color(ColorMode::Always, false));
+        assert!(!use_color(ColorMode::Always, true));
+        assert!(!use_color(ColorMode::Never, false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_repeats() -> Result<(), SolveError> {
+        let letter_freq = [('s', 0.3), ('t', 0.1), ('o', 0.1), ('m', 0.1), ('p', 0.1), ('a', 0.1),
+            ('y', 0.1), ('k', 0.1)]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let words = ["stomp", "sassy"];
+        let rank = |k: &Knowledge, count_repeats| {
+            best_candidates_avoiding(words.iter().copied(), k, &letter_freq, &[], 0., count_repeats)
+        };
+        let mut k = Knowledge::new(5);
+        assert_eq!(rank(&k, false), ["stomp", "sassy"]);
+        // Every letter of both is unknown, and the three s's make up for "sassy" having fewer
+        // different letters.
+        assert_eq!(rank(&k, true), ["sassy", "stomp"]);
+        // Once s and a are known, only the y in "sassy" says anything new.
+        k.add_infos(&parse_input("?s!k!a!t!e", 5)?, false)?;
+        assert_eq!(rank(&k, true), ["stomp", "sassy"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_results() -> io::Result<()> {
+        let results = [
+            WordResult {
+                word: "robot".to_owned(),
+                num_guesses: 2,
+                dict_size: 2315,
+                guesses: vec![("arose".to_owned(), 57), ("robot".to_owned(), 1)],
+            },
+            WordResult {
+                word: "zzzzz".to_owned(),
+                num_guesses: 2,
+                dict_size: 2315,
+                guesses: vec![("arose".to_owned(), 57), (String::new(), 0)],
+            },
+        ];
+
+        let mut out = vec![];
+        write_results(&results, OutputFormat::Json, &mut out)?;
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json, serde_json::json!([
+            {
+                "word": "robot",
+                "num_guesses": 2,
+                "dict_size": 2315,
+                "guesses": [
+                    {"guess": "arose", "remaining": 57},
+                    {"guess": "robot", "remaining": 1},
+                ],
+                "solved": true,
+                "failed": false,
+            },
+            {
+                "word": "zzzzz",
+                "num_guesses": 2,
+                "dict_size": 2315,
+                "guesses": [{"guess": "arose", "remaining": 57}],
+                "solved": false,
+                "failed": true,
+            },
+        ]));
+
+        let mut out = vec![];
+        write_results(&results, OutputFormat::Csv, &mut out)?;
+        assert_eq!(String::from_utf8(out).unwrap(), "word,num_guesses,dict_size,solved,guesses\n\
+            robot,2,2315,true,arose:57 robot:1\n\
+            zzzzz,2,2315,false,arose:57\n");
+
+        let mut out = vec![];
+        write_results(&results[..1], OutputFormat::Text, &mut out)?;
+        assert_eq!(String::from_utf8(out).unwrap(), "2 robot (2315) arose (57) robot (1)\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_when_confident() -> io::Result<()> {
+        let (dictionary, _) = small_fixture();
+        let weights = [("robot", 90.), ("motor", 5.), ("rotor", 5.), ("tutor", 1.)]
+            .into_iter()
+            .map(|(w, n)| (w.to_owned(), n))
+            .collect::<HashMap<_, _>>();
+
+        // "robot" isn't quite likely enough to begin with, but it is once "thorn" rules out
+        // "tutor".
+        let mut out = vec![];
+        let rows = play(&dictionary, "robot", false, Some((0.9, &weights)),
+            &mut "thorn\n".as_bytes(), &mut out)?;
+        let out = String::from_utf8(out).unwrap();
+        assert!(is_won(&rows));
+        assert_eq!(rows.len(), 2);
+        assert!(out.contains("Guess 2/6: robot (auto, 90% likely)"), "{}", out);
+
+        // Without it, input runs out.
+        let rows = play(&dictionary, "robot", false, None, &mut "thorn\n".as_bytes(),
+            &mut vec![])?;
+        assert_eq!(rows.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_guess() {
+        let (dictionary, letter_freq) = small_fixture();
+        // It doesn't have to be in the dictionary.
+        let first_guess = Some("crane".to_owned());
+        for word in &dictionary {
+            let guesses = guess_word_with_openers(word, first_guess.as_slice(),
+                dictionary.clone(), None, &letter_freq, &Strategy::Frequency, false, None);
+            assert_eq!(guesses[0].0, "crane");
+            assert_eq!(guesses.last().unwrap().0, *word);
+        }
+    }
+
+    #[test]
+    fn test_fetch_dictionary() -> io::Result<()> {
+        let dir = std::env::temp_dir().join(format!("wordle-cache-{}", std::process::id()));
+        let source = std::env::temp_dir().join(format!("wordle-url-{}.txt", std::process::id()));
+        std::fs::write(&source, "robot\nmotor\n")?;
+        let url = format!("file://{}", source.display());
+
+        let path = fetch_dictionary(&url, &dir, download)?;
+        assert!(path.starts_with(&dir));
+        assert_eq!(std::fs::read_to_string(&path)?, "robot\nmotor\n");
+
+        // When it can't be fetched, or isn't a word list, the copy from before is used.
+        let offline = |_: &str| Err(io::Error::other("offline"));
+        assert_eq!(fetch_dictionary(&url, &dir, offline)?, path);
+        assert_eq!(fetch_dictionary(&url, &dir, |_| Ok("\n\n".to_owned()))?, path);
+        assert_eq!(std::fs::read_to_string(&path)?, "robot\nmotor\n");
+
+        // A newer download replaces it.
+        assert_eq!(fetch_dictionary(&url, &dir, |_| Ok("rotor\n".to_owned()))?, path);
+        assert_eq!(std::fs::read_to_string(&path)?, "rotor\n");
+
+        // With nothing cached, the error comes through.
+        assert!(fetch_dictionary("file:///nonexistent", &dir, download).is_err());
+
+        std::fs::remove_dir_all(&dir)?;
+        std::fs::remove_file(&source)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_gray_restricts_own_position() -> Result<(), SolveError> {
+        // The second l is gray, but l is still in the word once.
+        let mut k = Knowledge::new(5);
+        let infos = check_guess("lodge", "llama");
+        assert_eq!(format_infos(&infos), "*l!l!a!m!a");
+        k.add_infos(&infos, false)?;
+        assert!(k.check_word("lodge", false));
+        assert_eq!(k.check_word_explain("lloyd"), Err(Rejection::Excluded { idx: 1, letter: 'l' }));
+        assert_eq!(k.check_word_explain("ovule"), Err(Rejection::NotGreen {
+            idx: 0, letter: 'o', green: 'l' }));
+
+        // A yellow s rules s out of the first position, but the gray s has to rule it out of the
+        // fourth one too.
+        let mut k = Knowledge::new(5);
+        let infos = check_guess("essay", "sissy");
+        assert_eq!(format_infos(&infos), "?s!i*s!s*y");
+        k.add_infos(&infos, false)?;
+        assert!(k.check_word("essay", false));
+        assert_eq!(k.check_word_explain("hussy"), Err(Rejection::Excluded { idx: 3, letter: 's' }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_all_words_parallel() -> io::Result<()> {
+        let (dictionary, _) = read_dictionary(BufReader::new(File::open("answers.txt")?), None)?;
+        let dictionary = dictionary.into_iter().step_by(10).collect::<BTreeSet<_>>();
+        let letter_freq = letter_frequencies(&dictionary);
+        let results = check_all_words_parallel(&dictionary, &letter_freq);
+        assert_eq!(results.iter().map(|r| &r.word).collect::<Vec<_>>(),
+            dictionary.iter().collect::<Vec<_>>());
+        for result in &results {
+            let serial = guess_word(&result.word, dictionary.clone(), None, &letter_freq,
+                &Strategy::Frequency, false);
+            assert_eq!(result.guesses, serial);
+            assert_eq!(result.num_guesses, serial.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve_words_parallel_order() {
+        let (dictionary, letter_freq) = small_fixture();
+        // Results come back in the order the words were given, not sorted, so a target list keeps
+        // its own order.
+        let words = dictionary.iter().rev().collect::<Vec<_>>();
+        let results = solve_words_parallel(&words, &dictionary, None, &[], &letter_freq,
+            &Strategy::Frequency, None);
+        assert_eq!(results.iter().map(|r| &r.word).collect::<Vec<_>>(), words);
+        for (result, word) in results.iter().zip(&words) {
+            assert_eq!(result.guesses.last().unwrap().0, **word);
+        }
+    }
+
+    #[test]
+    fn test_best_final_guesses() -> Result<(), SolveError> {
+        let answers = ["batch", "catch", "hatch", "latch", "match", "patch"];
+        let mut k = Knowledge::new(5);
+        k.add_infos(&parse_input("!w*a*t*c*h", 5)?, false)?;
+        let letter_freq = letter_frequencies(answers);
+
+        // A probe like "clamp" would narrow these down better, but can't win.
+        let probes = ["clamp", "batch", "catch", "hatch", "latch", "match", "patch"];
+        assert_eq!(Strategy::Entropy.rank_probes(&probes, &answers, &k, &letter_freq)[0],
+            "clamp");
+        let best = best_final_guesses(&probes, &k, &HashMap::new(), &letter_freq);
+        assert!(best.iter().all(|w| answers.contains(&w.as_str())));
+        assert_eq!(best.len(), answers.len());
+
+        // With weights, the most likely answer comes first.
+        let weights = [("match".to_owned(), 10.), ("patch".to_owned(), 1.)].into_iter().collect();
+        let best = best_final_guesses(&probes, &k, &weights, &letter_freq);
+        assert_eq!(best[0], "match");
+        assert!(best.iter().all(|w| answers.contains(&w.as_str())));
         Ok(())
     }
 }
@@ -0,0 +1,144 @@
+//! A minimal C interface to the solver. See `include/wordle_solve.h` for the C declarations.
+//! The crate only builds an rlib by default; build the shared library with
+//! `cargo rustc --release --lib --crate-type cdylib --features ffi`.
+//!
+//! Ownership rules: a solver returned by `wordle_solver_new` must be freed with
+//! `wordle_solver_free`, and a string returned by `wordle_solver_suggestions` must be freed with
+//! `wordle_string_free`. Strings passed in are only borrowed for the duration of the call.
+
+use crate::*;
+use std::collections::BTreeSet;
+use std::ffi::{c_char, c_int, CStr, CString};
+
+/// The solver state behind the opaque pointer handed out to C.
+pub struct FfiSolver {
+    candidates: BTreeSet<String>,
+    knowledge: Knowledge,
+    letter_freq: HashMap<char, f64>,
+    num_letters: usize,
+}
+
+/// Create a solver from a dictionary of newline-separated words, for words of the given length.
+/// Returns null if the dictionary isn't valid UTF-8.
+///
+/// # Safety
+///
+/// `words` must be a valid pointer to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn wordle_solver_new(words: *const c_char, num_letters: usize)
+    -> *mut FfiSolver
+{
+    let Ok(words) = CStr::from_ptr(words).to_str() else {
+        return std::ptr::null_mut();
+    };
+    let knowledge = Knowledge::new(num_letters);
+    let (candidates, letter_freq) =
+        Dictionary::from_words(words.lines().map(|word| word.trim().to_owned()), num_letters)
+            .into_parts();
+    Box::into_raw(Box::new(FfiSolver { candidates, knowledge, letter_freq, num_letters }))
+}
+
+/// Apply a row of feedback, in the same form the command line takes (e.g. `*c?r!a!n!e`).
+/// Returns 0 on success, or -1 if the row couldn't be parsed or contradicts earlier feedback.
+///
+/// # Safety
+///
+/// `solver` must be a pointer returned by `wordle_solver_new` which hasn't been freed, and `row`
+/// must be a valid pointer to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn wordle_solver_apply(solver: *mut FfiSolver, row: *const c_char) -> c_int {
+    let solver = &mut *solver;
+    let Ok(row) = CStr::from_ptr(row).to_str() else {
+        return -1;
+    };
+    let result = parse_input(row, solver.num_letters)
+        .and_then(|infos| solver.knowledge.add_infos(&infos, false));
+    if result.is_err() {
+        return -1;
+    }
+    let knowledge = &solver.knowledge;
+    solver.candidates.retain(|word| knowledge.check_word(word, false));
+    0
+}
+
+/// How many candidates are left.
+///
+/// # Safety
+///
+/// `solver` must be a pointer returned by `wordle_solver_new` which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn wordle_solver_candidate_count(solver: *const FfiSolver) -> usize {
+    (*solver).candidates.len()
+}
+
+/// Get up to `max` suggested guesses, best first, as a newline-separated string. The string must
+/// be freed with `wordle_string_free`.
+///
+/// # Safety
+///
+/// `solver` must be a pointer returned by `wordle_solver_new` which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn wordle_solver_suggestions(solver: *const FfiSolver, max: usize)
+    -> *mut c_char
+{
+    let solver = &*solver;
+    let best = best_candidates(solver.candidates.iter(), &solver.knowledge, &solver.letter_freq);
+    let list = best.iter().take(max).map(|w| w.as_str()).collect::<Vec<_>>().join("\n");
+    // Words with NULs in them can't get through check_word, so this can't fail.
+    CString::new(list).unwrap().into_raw()
+}
+
+/// Free a string returned by `wordle_solver_suggestions`. Does nothing if it's null.
+///
+/// # Safety
+///
+/// `s` must be null, or a pointer returned by `wordle_solver_suggestions` which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn wordle_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Free a solver. Does nothing if it's null.
+///
+/// # Safety
+///
+/// `solver` must be null, or a pointer returned by `wordle_solver_new` which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn wordle_solver_free(solver: *mut FfiSolver) {
+    if !solver.is_null() {
+        drop(Box::from_raw(solver));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ffi_round_trip() {
+        let words = CString::new("robot\nmotor\nrotor\nsorts\nthorn\ncrane\n").unwrap();
+        unsafe {
+            let solver = wordle_solver_new(words.as_ptr(), 5);
+            assert!(!solver.is_null());
+            assert_eq!(wordle_solver_candidate_count(solver), 6);
+
+            let row = CString::new("?t!h?o?r!n").unwrap();
+            assert_eq!(wordle_solver_apply(solver, row.as_ptr()), 0);
+            assert_eq!(wordle_solver_candidate_count(solver), 4);
+
+            let bad = CString::new("#t!h?o?r!n").unwrap();
+            assert_eq!(wordle_solver_apply(solver, bad.as_ptr()), -1);
+            assert_eq!(wordle_solver_candidate_count(solver), 4);
+
+            let suggestions = wordle_solver_suggestions(solver, 2);
+            let list = CStr::from_ptr(suggestions).to_str().unwrap().to_owned();
+            wordle_string_free(suggestions);
+            assert_eq!(list.lines().count(), 2);
+            assert!(list.lines().all(|w| ["motor", "robot", "rotor", "sorts"].contains(&w)));
+
+            wordle_solver_free(solver);
+        }
+    }
+}
@@ -1,218 +1,1777 @@
+//! A Wordle solver.
+//!
+//! The solver can be driven entirely in memory: start with a [`Knowledge`] for the word length,
+//! pick a guess from the candidates with [`best_candidates`] (or a [`Strategy`]), get feedback for
+//! it (from the game, or from [`check_guess`] if you know the answer), apply the feedback with
+//! [`Knowledge::add_infos`], and keep only the candidates that pass [`Knowledge::check_word`].
+//! See `examples/embed.rs` for a complete program, or [`guess_word`] for the whole loop when the
+//! answer is known.
+//!
+//! ```
+//! use wordle_solve::*;
+//!
+//! let mut candidates = vec!["crane", "robot", "motor", "rotor", "thorn"];
+//! let letter_freq = letter_frequencies(&candidates);
+//! let mut knowledge = Knowledge::new(5);
+//! let mut guesses = 0;
+//! loop {
+//!     let guess = best_candidates(candidates.iter().copied(), &knowledge, &letter_freq)[0];
+//!     guesses += 1;
+//!     if guess == "rotor" {
+//!         break;
+//!     }
+//!     knowledge.add_infos(&check_guess("rotor", guess), false).unwrap();
+//!     candidates.retain(|word| knowledge.check_word(word, false));
+//! }
+//! assert!(guesses <= 3);
+//! ```
+
 use std::cmp::Ordering;
 use std::collections::hash_map::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::str::FromStr;
+use rayon::prelude::*;
+
+mod api;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod multi;
+mod output;
+mod pattern;
+mod solver;
+mod strategy;
+mod tree;
+pub use api::*;
+pub use multi::*;
+pub use output::*;
+pub use pattern::*;
+pub use solver::*;
+pub use strategy::*;
+pub use tree::*;
 
 /// Represents one letter tile.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Info {
     /// Green letters
     Exact(char),
 
-    /// Yellow letters
-    Somewhere(char),
+    /// Yellow letters
+    Somewhere(char),
+
+    /// Gray letters
+    No(char),
+}
+
+/// The color of one tile, without its letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    Green,
+    Yellow,
+    Gray,
+}
+
+impl Pattern {
+    /// The feedback for the given letter shown in this color.
+    pub fn info(self, c: char) -> Info {
+        match self {
+            Pattern::Green => Info::Exact(c),
+            Pattern::Yellow => Info::Somewhere(c),
+            Pattern::Gray => Info::No(c),
+        }
+    }
+}
+
+/// Ways that feedback (or something deduced) can contradict what's already known, or not fit the
+/// word, when adding it to a [`Knowledge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KnowledgeError {
+    /// A position already known to be one letter was said to be a different one.
+    ConflictingExact { position: usize, existing: char, new: char },
+
+    /// A letter was said not to be at a position where it's already known to be.
+    ExactPositionIsYellow { position: usize, letter: char },
+
+    /// Feedback was given for the wrong number of letters.
+    LengthMismatch { expected: usize, actual: usize },
+
+    /// A position was given which is past the end of the word.
+    PositionOutOfRange { position: usize, num_letters: usize },
+}
+
+impl std::fmt::Display for KnowledgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KnowledgeError::ConflictingExact { position, existing, new } => {
+                write!(f, "you already said that letter {} is {:?}, not {:?}", position, existing,
+                    new)
+            }
+            KnowledgeError::ExactPositionIsYellow { position, letter } => {
+                write!(f, "you already said that letter {} is {:?}", position, letter)
+            }
+            KnowledgeError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {} letters, not {}", expected, actual)
+            }
+            KnowledgeError::PositionOutOfRange { position, num_letters } => {
+                write!(f, "position {} is out of range for {} letters", position, num_letters)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KnowledgeError {}
+
+/// Things that can go wrong when parsing or applying feedback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The feedback doesn't fit what's already known.
+    Knowledge(KnowledgeError),
+
+    /// More letters were given than the word has.
+    TooManyLetters,
+
+    /// Feedback was given for the wrong number of letters.
+    LengthMismatch { expected: usize, actual: usize },
+
+    /// A letter was annotated with something other than `*`, `?` or `!`.
+    UnknownFlag(char),
+
+    /// An annotation was left over at the end of the input with no letter after it.
+    UnprocessedInput(char),
+
+    /// A game API's response couldn't be understood.
+    BadApiResponse(String),
+
+    /// A letter in pasted colored text had no green, yellow or gray background.
+    Uncolored(char),
+
+    /// A character in a row of share text emoji wasn't a green, yellow, black or white square.
+    UnknownTile(char),
+
+    /// In hard mode, a guess was made which doesn't fit what's already known.
+    HardModeViolation { guess: String, violated_constraint: Rejection },
+
+    /// Feedback for words this long can't be encoded as a number; see [`MAX_PATTERN_LETTERS`].
+    PatternTooLong { num_letters: usize },
+
+    /// While solving for a word, the true feedback for the guesses ruled the word out.
+    RuledOut { word: String, guesses: Vec<String> },
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Knowledge(e) => e.fmt(f),
+            SolveError::TooManyLetters => f.write_str("too many letters in input"),
+            SolveError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {} letters, not {}", expected, actual)
+            }
+            SolveError::UnknownFlag(c) => write!(f, "unknown annotation {:?}", c),
+            SolveError::UnprocessedInput(c) => write!(f, "unprocessed input {:?}", c),
+            SolveError::BadApiResponse(e) => write!(f, "bad API response: {}", e),
+            SolveError::Uncolored(c) => write!(f, "no tile color for letter {:?}", c),
+            SolveError::UnknownTile(c) => write!(f, "{:?} isn't a tile color", c),
+            SolveError::HardModeViolation { guess, violated_constraint } => {
+                write!(f, "{:?} isn't allowed in hard mode: it {}", guess, violated_constraint)
+            }
+            SolveError::PatternTooLong { num_letters } => {
+                write!(f, "can't encode feedback for {} letters; the most is {}", num_letters,
+                    MAX_PATTERN_LETTERS)
+            }
+            SolveError::RuledOut { word, guesses } => {
+                write!(f, "{} was ruled out after guessing {}", word, guesses.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl From<KnowledgeError> for SolveError {
+    fn from(e: KnowledgeError) -> Self {
+        SolveError::Knowledge(e)
+    }
+}
+
+/// Represents everything known about the game state.
+#[derive(Debug, Clone)]
+pub struct Knowledge {
+    /// Restrictions on which letters can go in which spaces.
+    restrictions: Vec<Restriction>,
+
+    /// Letters that must appear *somewhere* in the word (and how many times).
+    must_have: HashMap<char, usize>,
+
+    /// Letters that can appear at most so many times, learned from a guess where some copies of
+    /// the letter were colored and another was gray.
+    max_have: HashMap<char, usize>,
+
+    /// Whether a gray tile means the letter is absent from the whole word (as in Wordle), or only
+    /// from that one position (as in some more lenient clones).
+    gray_means_absent: bool,
+
+    /// Which words are valid at all, regardless of any other knowledge.
+    validity: fn(&str) -> bool,
+}
+
+/// Why a word can't be the answer, from [`Knowledge::check_word_explain`]. Positions count from
+/// zero, but are shown counting from one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// The word has the wrong number of letters.
+    WrongLength { expected: usize, actual: usize },
+
+    /// The word isn't valid at all (by default, it isn't all lowercase letters a to z).
+    Invalid,
+
+    /// A letter is in a position where a different letter is known to be green.
+    NotGreen { idx: usize, letter: char, green: char },
+
+    /// A letter is in a position it's been ruled out of.
+    Excluded { idx: usize, letter: char },
+
+    /// A letter known to be in the word is missing, or not there enough times.
+    Missing { letter: char, need: usize, have: usize },
+
+    /// A letter is there more times than it can be.
+    TooMany { letter: char, max: usize, have: usize },
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::WrongLength { expected, actual } => {
+                write!(f, "has {} letters, not {}", actual, expected)
+            }
+            Rejection::Invalid => f.write_str("isn't a valid word"),
+            Rejection::NotGreen { idx, letter, green } => {
+                write!(f, "has {:?} at position {}, but {:?} is green there", letter, idx + 1,
+                    green)
+            }
+            Rejection::Excluded { idx, letter } => {
+                write!(f, "has {:?} at position {}, which is ruled out there", letter, idx + 1)
+            }
+            Rejection::Missing { letter, need, have } => {
+                write!(f, "needs {:?} {} time(s), but has it {} time(s)", letter, need, have)
+            }
+            Rejection::TooMany { letter, max, have } => {
+                write!(f, "can have {:?} at most {} time(s), but has it {} time(s)", letter, max,
+                    have)
+            }
+        }
+    }
+}
+
+/// A restriction on a letter at a particular position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Restriction {
+    /// Letter must be exactly the given letter.
+    Exact(char),
+
+    /// Letter must not be any of the given letters.
+    Not(LetterSet),
+}
+
+/// A set of letters. The letters a to z are kept as a bitmask, so checking for them is cheap; any
+/// others (for custom alphabets) are kept in a list.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct LetterSet {
+    mask: u32,
+    others: Vec<char>,
+}
+
+impl LetterSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bit(c: char) -> Option<u32> {
+        c.is_ascii_lowercase().then(|| 1 << (c as u32 - 'a' as u32))
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        match Self::bit(c) {
+            Some(bit) => self.mask & bit != 0,
+            None => self.others.contains(&c),
+        }
+    }
+
+    /// Add a letter to the set, if it isn't already in it.
+    pub fn insert(&mut self, c: char) {
+        match Self::bit(c) {
+            Some(bit) => self.mask |= bit,
+            None if !self.others.contains(&c) => self.others.push(c),
+            None => (),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mask == 0 && self.others.is_empty()
+    }
+
+    /// The letters in the set: a to z in order, then any others in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = char> + '_ {
+        ('a' ..= 'z')
+            .filter(|&c| self.contains(c))
+            .chain(self.others.iter().copied())
+    }
+}
+
+impl std::fmt::Debug for LetterSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// The default word validity rule: only lowercase letters a to z.
+pub fn is_lowercase_word(word: &str) -> bool {
+    word.chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// The new constraints some feedback would add to a [`Knowledge`], from [`Knowledge::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KnowledgeDiff {
+    /// Positions which would become known, and their letters.
+    pub new_exact: Vec<(usize, char)>,
+
+    /// Letters which would be newly ruled out at positions.
+    pub new_excluded: Vec<(usize, char)>,
+
+    /// Letters whose minimum count would be raised, and their new minimums.
+    pub raised_counts: Vec<(char, usize)>,
+}
+
+impl KnowledgeDiff {
+    /// Whether the feedback would teach us nothing new.
+    pub fn is_empty(&self) -> bool {
+        self.new_exact.is_empty() && self.new_excluded.is_empty() && self.raised_counts.is_empty()
+    }
+}
+
+/// What is known about a letter overall, as shown on the game's on-screen keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStatus {
+    /// The letter has been placed in at least one position (green).
+    Placed,
+
+    /// The letter is in the word, but hasn't been placed yet (yellow).
+    Present,
+
+    /// The letter isn't in the word (gray).
+    Absent,
+
+    /// Nothing is known about the letter yet.
+    Unknown,
+}
+
+/// Rank candidate guesses: words with the most unique letters first, and then among those, the
+/// ones with the most common letters we don't know anything about yet.
+///
+/// Returns at least 10 words if there are that many. If `letter_freq` is empty, words with the same
+/// number of unique letters are ranked alphabetically instead. This is [`FrequencyStrategy`], for
+/// any kind of words.
+pub fn best_candidates<I, W>(
+    candidates: I,
+    knowledge: &Knowledge,
+    letter_freq: &HashMap<char, f64>,
+) -> Vec<<W as ToOwned>::Owned>
+    where I: Iterator<Item=W>,
+          W: AsRef<str> + ToOwned,
+{
+    best_candidates_avoiding(candidates, knowledge, letter_freq, &[], 0., false)
+}
+
+/// How letters are valued when ranking guesses by letter frequency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoringMode {
+    /// By how common the letter is anywhere in the words, as with [`best_candidates`].
+    Global,
+
+    /// By how common the letter is in the position it's in, as with
+    /// [`best_candidates_with_position_freq`].
+    Position,
+}
+
+impl FromStr for ScoringMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "global" => Ok(ScoringMode::Global),
+            "position" => Ok(ScoringMode::Position),
+            other => Err(format!("unknown scoring mode {:?}", other)),
+        }
+    }
+}
+
+/// How common a letter counts as in [`best_candidates_with_position_freq`] if it's never seen in
+/// its position, or is already known about.
+const POSITION_FREQ_FLOOR: f64 = 1e-6;
+
+/// Like [`best_candidates`], but each letter we don't know anything about yet is valued by how
+/// common it is in the position it's in (from [`build_position_freq`]), rather than anywhere in
+/// the word. A letter which is common where it is is more likely to come up green. The values for
+/// each position are multiplied together (by adding their logs), with letters never seen in their
+/// position, and letters already known about, counting as [`POSITION_FREQ_FLOOR`]. Words with the
+/// most unique letters still come first. Returns the best 10.
+pub fn best_candidates_with_position_freq<I, W>(
+    candidates: I,
+    knowledge: &Knowledge,
+    position_freq: &[HashMap<char, f64>],
+) -> Vec<<W as ToOwned>::Owned>
+    where I: Iterator<Item=W>,
+          W: AsRef<str> + ToOwned,
+{
+    let score = |word: &str| {
+        word.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let freq = match knowledge.key_status(c) {
+                    KeyStatus::Unknown => {
+                        position_freq.get(i).and_then(|freq| freq.get(&c)).copied().unwrap_or(0.)
+                    }
+                    _ => 0.,
+                };
+                freq.max(POSITION_FREQ_FLOOR).ln()
+            })
+            .sum::<f64>()
+    };
+    let mut scored = candidates
+        .map(|word| {
+            let unique = unique_letters(word.as_ref());
+            let score = NonNan::try_from(score(word.as_ref())).unwrap();
+            (word, unique, score)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|(w1, u1, s1), (w2, u2, s2)| {
+        u2.cmp(u1).then(s2.cmp(s1)).then_with(|| w1.as_ref().cmp(w2.as_ref()))
+    });
+    scored.into_iter().take(10).map(|(word, _, _)| word.to_owned()).collect()
+}
+
+/// Like [`best_candidates`], but with a penalty for reusing letters from `recent_letters` (usually
+/// the previous guess) which haven't turned out to be in the word, to nudge towards trying new
+/// letters. Each such letter counts against a word by `penalty` times its frequency, instead of
+/// counting for zero.
+///
+/// With `count_repeats`, words are grouped by how many of their letters (counting repeats) aren't
+/// known about yet, instead of by how many unique letters they have. In long words, a repeated
+/// letter can still say something about where it goes.
+pub fn best_candidates_avoiding<I, W>(
+    candidates: I,
+    knowledge: &Knowledge,
+    letter_freq: &HashMap<char, f64>,
+    recent_letters: &[char],
+    penalty: f64,
+    count_repeats: bool,
+) -> Vec<<W as ToOwned>::Owned>
+    where I: Iterator<Item=W>,
+          W: AsRef<str> + ToOwned,
+{
+    FrequencyStrategy::rank_words(candidates, knowledge, letter_freq, recent_letters, penalty,
+        count_repeats)
+        .into_iter()
+        .map(|(word, _)| word.to_owned())
+        .collect()
+}
+
+/// Rank guesses from `all_words` by how much information they're expected to give: the entropy, in
+/// bits, of the feedback they'd get, over the candidates which still fit the knowledge. Like
+/// [`best_candidates`], but slower and less greedy. Returns the best 10, with their scores.
+pub fn best_candidates_by_entropy<W, G>(candidates: &[W], knowledge: &Knowledge, all_words: &[G])
+    -> Vec<(String, f64)>
+    where W: AsRef<str>,
+          G: AsRef<str>,
+{
+    let answers = candidates.iter()
+        .map(|w| w.as_ref())
+        .filter(|w| knowledge.check_word(w, false))
+        .collect::<Vec<_>>();
+    let mut ranked = rank_by_split(all_words, &answers, SplitMetric::Entropy);
+    ranked.truncate(10);
+    ranked
+}
+
+/// Rank guesses by the most candidates that could be left after guessing them, whatever the
+/// feedback: the size of the largest group they split the candidates into. Fewest first, with ties
+/// going to the guess that splits them into more groups, then to whichever came first. Returns all
+/// of them, with their worst cases.
+///
+/// This compares every guess against every candidate, so it stops as soon as it finds a guess
+/// which tells the candidates all apart: nothing can do better. The guesses after that one aren't
+/// scored, and come last, in the order given, with `None`.
+pub fn rank_by_worst_case<G, W>(guesses: &[G], candidates: &[W]) -> Vec<(String, Option<usize>)>
+    where G: AsRef<str>,
+          W: AsRef<str>,
+{
+    let mut scored = vec![];
+    for guess in guesses {
+        let sizes = split_sizes(guess.as_ref(), candidates);
+        let worst = sizes.iter().copied().max().unwrap_or(0);
+        scored.push((guess.as_ref().to_owned(), worst, sizes.len()));
+        if worst <= 1 {
+            break;
+        }
+    }
+    let unscored = guesses[scored.len() ..].iter().map(|guess| (guess.as_ref().to_owned(), None));
+    scored.sort_by_key(|&(_, worst, groups)| (worst, std::cmp::Reverse(groups)));
+    scored.into_iter()
+        .map(|(word, worst, _)| (word, Some(worst)))
+        .chain(unscored)
+        .collect()
+}
+
+/// Rank the candidates which still fit the knowledge by their worst case, as
+/// [`rank_by_worst_case`] does. Better than [`best_candidates_by_entropy`] at avoiding bad luck,
+/// but worse on average. Returns the best 10, with their worst cases, or fewer if one of them
+/// tells all the candidates apart, as the ones after it aren't scored.
+pub fn best_candidates_minimax<W: AsRef<str>>(candidates: &[W], knowledge: &Knowledge)
+    -> Vec<(String, usize)>
+{
+    let answers = candidates.iter()
+        .map(|w| w.as_ref())
+        .filter(|w| knowledge.check_word(w, false))
+        .collect::<Vec<_>>();
+    rank_by_worst_case(&answers, &answers)
+        .into_iter()
+        .take(10)
+        .map_while(|(word, worst)| Some((word, worst?)))
+        .collect()
+}
+
+/// The most candidates [`best_candidates_lookahead2`] will look two guesses ahead for.
+pub const LOOKAHEAD2_MAX_CANDIDATES: usize = 200;
+
+/// Rank the candidates which still fit the knowledge by how many candidates are expected to be
+/// left after guessing them and then the best possible second guess, like
+/// [`Strategy::Lookahead`]. That's cubic in the number of candidates, so it's meant for the
+/// endgame: with more than [`LOOKAHEAD2_MAX_CANDIDATES`], it only looks one guess ahead. Returns
+/// all of them, best first, with the expected number left.
+pub fn best_candidates_lookahead2<W: AsRef<str>>(candidates: &[W], knowledge: &Knowledge)
+    -> Vec<(String, f64)>
+{
+    let answers = candidates.iter()
+        .map(|w| w.as_ref())
+        .filter(|w| knowledge.check_word(w, false))
+        .collect::<Vec<_>>();
+    let ranked = if answers.len() > LOOKAHEAD2_MAX_CANDIDATES {
+        rank_by_split(&answers, &answers, SplitMetric::ExpectedRemaining)
+    } else {
+        rank_by_lookahead(&answers, &answers)
+    };
+    ranked.into_iter().map(|(word, score)| (word, -score)).collect()
+}
+
+/// Rank guesses for "normal mode", where any word can be guessed even if it can't be the answer:
+/// words from `guess_pool` are ranked by how well they split up the words in `candidate_pool`
+/// which still fit the knowledge, with ties going to words which could be the answer. Once there
+/// are only one or two possible answers left, nothing beats guessing one of them, so they're
+/// ranked by [`best_candidates`] instead. Returns the best 10.
+pub fn best_candidates_unrestricted<G, W>(
+    guess_pool: &[G],
+    candidate_pool: &[W],
+    knowledge: &Knowledge,
+    letter_freq: &HashMap<char, f64>,
+) -> Vec<String>
+    where G: AsRef<str>,
+          W: AsRef<str>,
+{
+    let answers = candidate_pool.iter()
+        .map(|w| w.as_ref())
+        .filter(|w| knowledge.check_word(w, false))
+        .collect::<Vec<_>>();
+    if answers.len() <= 2 {
+        return best_candidates(answers.into_iter(), knowledge, letter_freq)
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+    }
+    rank_by_split(guess_pool, &answers, SplitMetric::Entropy)
+        .into_iter()
+        .take(10)
+        .map(|(word, _)| word)
+        .collect()
+}
+
+/// The format of a dictionary file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DictFormat {
+    /// One word per line.
+    Plain,
+
+    /// `word,count` on each line, optionally with a `word,count` header line.
+    Csv,
+}
+
+impl FromStr for DictFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(DictFormat::Plain),
+            "csv" => Ok(DictFormat::Csv),
+            other => Err(format!("unknown dictionary format {:?}", other)),
+        }
+    }
+}
+
+/// Read a dictionary, returning its words and, for CSV dictionaries, each word's frequency weight.
+///
+/// If no format is given, it's detected by looking for a `word,...` CSV header on the first line.
+pub fn read_dictionary(reader: impl BufRead, format: Option<DictFormat>)
+    -> io::Result<(Vec<String>, HashMap<String, f64>)>
+{
+    let mut words = vec![];
+    let mut weights = HashMap::new();
+    let mut format = format;
+    for (i, res) in reader.lines().enumerate() {
+        let line = res?;
+        if i == 0 {
+            let is_header = line.to_ascii_lowercase().starts_with("word,");
+            if format.is_none() {
+                format = Some(if is_header { DictFormat::Csv } else { DictFormat::Plain });
+            }
+            if is_header && format == Some(DictFormat::Csv) {
+                continue;
+            }
+        }
+        if format == Some(DictFormat::Csv) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let bad_line = || io::Error::new(io::ErrorKind::InvalidData,
+                format!("line {}: expected \"word,count\", not {:?}", i + 1, line));
+            let (word, count) = line.split_once(',').ok_or_else(bad_line)?;
+            let count = count.trim().parse::<f64>().map_err(|_| bad_line())?;
+            weights.insert(word.trim().to_owned(), count);
+            words.push(word.trim().to_owned());
+        } else {
+            words.push(line);
+        }
+    }
+    Ok((words, weights))
+}
+
+/// Words grouped by their length in characters, so that only the words of the right length ever
+/// need looking at, however many others the dictionary has.
+#[derive(Debug, Clone, Default)]
+pub struct LengthBuckets {
+    buckets: BTreeMap<usize, Vec<String>>,
+}
+
+impl LengthBuckets {
+    pub fn new(words: impl IntoIterator<Item = String>) -> Self {
+        let mut buckets = BTreeMap::<usize, Vec<String>>::new();
+        for word in words {
+            buckets.entry(word.chars().count()).or_default().push(word);
+        }
+        Self { buckets }
+    }
+
+    /// The words with the given number of characters.
+    pub fn of_length(&self, len: usize) -> &[String] {
+        self.buckets.get(&len).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Take out the words with the given number of characters.
+    pub fn take(&mut self, len: usize) -> Vec<String> {
+        self.buckets.remove(&len).unwrap_or_default()
+    }
+}
+
+/// The words of one length that the solver knows about, and how often each letter occurs in them,
+/// which is what [`best_candidates`] and the [`Strategy`]s score guesses by.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: BTreeSet<String>,
+    letter_freq: HashMap<char, f64>,
+}
+
+impl Dictionary {
+    /// Keep only the words with the given number of letters which are valid words (see
+    /// [`Knowledge::new`]). They're kept in order, which makes debugging easier.
+    pub fn from_words(words: impl IntoIterator<Item=String>, num_letters: usize) -> Self {
+        let knowledge = Knowledge::new(num_letters);
+        let words = LengthBuckets::new(words)
+            .take(num_letters)
+            .into_iter()
+            .filter(|word| knowledge.check_word(word, false))
+            .collect::<BTreeSet<_>>();
+        let letter_freq = letter_frequencies(&words);
+        Self { words, letter_freq }
+    }
+
+    /// Read a dictionary file, in either format (see [`read_dictionary`]). Any frequency weights
+    /// in it are ignored.
+    pub fn load(path: &Path, num_letters: usize) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let (words, _) = read_dictionary(io::BufReader::new(file), None)?;
+        Ok(Self::from_words(words, num_letters))
+    }
+
+    /// Set whether guesses are scored by letter frequency at all (the default). If not, there are
+    /// no letter frequencies, so every letter counts the same.
+    pub fn with_frequency(mut self, frequency: bool) -> Self {
+        self.letter_freq = if frequency {
+            letter_frequencies(&self.words)
+        } else {
+            HashMap::new()
+        };
+        self
+    }
+
+    pub fn words(&self) -> &BTreeSet<String> {
+        &self.words
+    }
+
+    pub fn letter_freq(&self) -> &HashMap<char, f64> {
+        &self.letter_freq
+    }
+
+    /// Add more words, which should already be the right length, and work out the letter
+    /// frequencies again.
+    pub fn extend(&mut self, words: impl IntoIterator<Item=String>) {
+        self.words.extend(words);
+        self.letter_freq = letter_frequencies(&self.words);
+    }
+
+    /// Just the words which fit what's known. The letter frequencies are still those of the whole
+    /// dictionary, not only these words.
+    pub fn filter(&self, knowledge: &Knowledge) -> Self {
+        Self {
+            words: self.words.iter()
+                .filter(|word| knowledge.check_word(word, false))
+                .cloned()
+                .collect(),
+            letter_freq: self.letter_freq.clone(),
+        }
+    }
+
+    /// The words and letter frequencies, for when they go separate ways: usually the words get
+    /// narrowed down to the candidates, while the frequencies stay the same.
+    pub fn into_parts(self) -> (BTreeSet<String>, HashMap<char, f64>) {
+        (self.words, self.letter_freq)
+    }
+}
+
+/// The probability of each candidate being the answer, according to the words' frequency weights,
+/// most likely first.
+///
+/// Candidates without a weight count as zero, unless none of them have one, in which case they're
+/// all equally likely.
+pub fn candidate_probabilities<W: AsRef<str>>(candidates: &[W], weights: &HashMap<String, f64>)
+    -> Vec<(String, f64)>
+{
+    let mut probs = candidates.iter()
+        .map(|w| (w.as_ref().to_owned(), weights.get(w.as_ref()).copied().unwrap_or(0.)))
+        .collect::<Vec<_>>();
+    let total = probs.iter().map(|(_, weight)| weight).sum::<f64>();
+    for (_, p) in &mut probs {
+        *p = if total > 0. { *p / total } else { 1. / candidates.len() as f64 };
+    }
+    probs.sort_by_key(|(_, p)| std::cmp::Reverse(NonNan::try_from(*p).unwrap()));
+    probs
+}
+
+/// The best guesses to make when it's the last one allowed. Only a candidate can win then, so no
+/// other words are suggested: the candidates which still fit the knowledge are ranked by how likely
+/// they are, using the words' frequency weights, or if none of them have a weight, by
+/// [`best_candidates`].
+pub fn best_final_guesses<W: AsRef<str>>(
+    candidates: &[W],
+    knowledge: &Knowledge,
+    weights: &HashMap<String, f64>,
+    letter_freq: &HashMap<char, f64>,
+) -> Vec<String> {
+    let answers = candidates.iter()
+        .map(|w| w.as_ref())
+        .filter(|w| knowledge.check_word(w, false))
+        .collect::<Vec<_>>();
+    if answers.iter().any(|w| weights.contains_key(*w)) {
+        candidate_probabilities(&answers, weights).into_iter().map(|(word, _)| word).collect()
+    } else {
+        best_candidates(answers.into_iter(), knowledge, letter_freq)
+            .into_iter()
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+/// Returns a key shared by all anagrams of a word: its letters, sorted.
+pub fn anagram_key(word: &str) -> String {
+    let mut letters = word.chars().collect::<Vec<_>>();
+    letters.sort_unstable();
+    letters.into_iter().collect()
+}
+
+/// Collapse anagrams in a ranked list of words down to one representative per group.
+///
+/// Each group keeps the position of its highest-ranked member, but is always represented by its
+/// alphabetically-first word, so the output doesn't depend on how ties happened to get sorted.
+/// That's only right for rankings where anagrams always tie; see [`collapse_anagrams_ranked`] for
+/// others. Returns each representative along with the size of its group.
+pub fn collapse_anagrams<W: AsRef<str>>(words: &[W]) -> Vec<(String, usize)> {
+    collapse_anagrams_by(words, true)
+}
+
+/// Like [`collapse_anagrams`], but each group is represented by its highest-ranked member, for
+/// rankings where anagrams don't necessarily tie.
+pub fn collapse_anagrams_ranked<W: AsRef<str>>(words: &[W]) -> Vec<(String, usize)> {
+    collapse_anagrams_by(words, false)
+}
+
+fn collapse_anagrams_by<W: AsRef<str>>(words: &[W], alphabetical: bool) -> Vec<(String, usize)> {
+    let mut groups = Vec::<(String, usize)>::new();
+    let mut index_by_key = HashMap::<String, usize>::new();
+    for word in words {
+        let word = word.as_ref();
+        match index_by_key.entry(anagram_key(word)) {
+            Entry::Occupied(entry) => {
+                let (rep, count) = &mut groups[*entry.get()];
+                if alphabetical && word < rep.as_str() {
+                    *rep = word.to_owned();
+                }
+                *count += 1;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(groups.len());
+                groups.push((word.to_owned(), 1));
+            }
+        }
+    }
+    groups
+}
+
+/// How to score the way a guess splits the candidates up by the feedback pattern it would get.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitMetric {
+    /// Expected information gained, in bits.
+    Entropy,
+
+    /// Expected number of candidates remaining afterwards.
+    ExpectedRemaining,
+
+    /// Number of candidates remaining afterwards in the worst case.
+    WorstCase,
+}
+
+impl SplitMetric {
+    /// Score a distribution of feedback patterns (as from [`pattern_distribution`]). Higher
+    /// scores are better.
+    pub fn score(&self, distribution: &HashMap<Vec<Info>, usize>) -> f64 {
+        self.score_sizes(distribution.values().copied().collect())
+    }
+
+    /// Score a split given just the sizes of the groups, in any order.
+    pub(crate) fn score_sizes(&self, mut sizes: Vec<usize>) -> f64 {
+        // Sum in a fixed order, so equally good splits get exactly equal scores.
+        sizes.sort_unstable();
+        let total = sizes.iter().sum::<usize>() as f64;
+        match self {
+            SplitMetric::Entropy => sizes.iter()
+                .map(|&n| {
+                    let p = n as f64 / total;
+                    -p * p.log2()
+                })
+                .sum(),
+            SplitMetric::ExpectedRemaining => -sizes.iter()
+                .map(|&n| (n * n) as f64 / total)
+                .sum::<f64>(),
+            SplitMetric::WorstCase => -(sizes.last().copied().unwrap_or(0) as f64),
+        }
+    }
+}
+
+/// The sizes of the groups the candidates are split into by the feedback for the guess. Much
+/// cheaper than [`pattern_distribution`], as the feedback is boiled down to a number (see
+/// [`encode_pattern`]). Words can be up to 80 letters long.
+pub(crate) fn split_sizes<W: AsRef<str>>(guess: &str, candidates: &[W]) -> Vec<usize> {
+    let feedback = candidates.iter().map(|word| check_guess(word.as_ref(), guess));
+    if guess.chars().count() <= MAX_PATTERN_LETTERS {
+        group_sizes(feedback.map(|infos| encode_pattern(&infos)))
+    } else {
+        group_sizes(feedback.map(|infos| encode_long_pattern(&infos)))
+    }
+}
+
+/// How many times each distinct value comes up, in order of the values.
+pub(crate) fn group_sizes<T: Ord>(values: impl Iterator<Item=T>) -> Vec<usize> {
+    let mut values = values.collect::<Vec<_>>();
+    values.sort_unstable();
+    values.chunk_by(|a, b| a == b).map(|group| group.len()).collect()
+}
+
+/// Count how many of the candidates would produce each feedback pattern for the given guess.
+pub fn pattern_distribution<I, W>(guess: &str, candidates: I) -> HashMap<Vec<Info>, usize>
+    where I: IntoIterator<Item=W>,
+          W: AsRef<str>,
+{
+    let mut distribution = HashMap::new();
+    for word in candidates {
+        *distribution.entry(check_guess(word.as_ref(), guess)).or_insert(0) += 1;
+    }
+    distribution
+}
+
+/// Whether a guess could get different feedback depending on which of the candidates is the
+/// answer. If not, the guess would teach us nothing.
+pub fn is_informative<W: AsRef<str>>(guess: &str, candidates: &[W]) -> bool {
+    let mut patterns = candidates.iter().map(|word| check_guess(word.as_ref(), guess));
+    match patterns.next() {
+        Some(first) => patterns.any(|p| p != first),
+        None => false,
+    }
+}
+
+/// Move any suggestions which would get the same feedback for every candidate (and therefore
+/// teach us nothing) after all the informative ones, keeping the order otherwise.
+///
+/// A suggestion which is itself a candidate is always informative when there's more than one
+/// candidate, so this only matters when suggestions can come from outside the candidates.
+pub fn demote_uninformative<S, W>(suggestions: &mut Vec<S>, candidates: &[W])
+    where S: AsRef<str>,
+          W: AsRef<str>,
+{
+    if candidates.len() < 2 {
+        return;
+    }
+    let (mut informative, uninformative): (Vec<S>, Vec<S>) = suggestions.drain(..)
+        .partition(|guess| is_informative(guess.as_ref(), candidates));
+    informative.extend(uninformative);
+    *suggestions = informative;
+}
+
+/// How many different letters are in the word.
+pub fn unique_letters(word: &str) -> usize {
+    let mut letters = word.chars().collect::<Vec<_>>();
+    letters.sort_unstable();
+    letters.dedup();
+    letters.len()
+}
+
+/// Drop any suggestions with fewer than `min` different letters, as long as that leaves some.
+pub fn require_unique_letters<S: AsRef<str>>(suggestions: &mut Vec<S>, min: usize) {
+    if suggestions.iter().any(|word| unique_letters(word.as_ref()) >= min) {
+        suggestions.retain(|word| unique_letters(word.as_ref()) >= min);
+    }
+}
+
+/// The letters typed with the left hand on a QWERTY keyboard, as drawn by [`render_keyboard`].
+const LEFT_HAND_KEYS: &str = "qwertasdfgzxcvb";
+
+/// How many times typing the word on a QWERTY keyboard takes the same hand for two letters in a
+/// row. Letters which aren't on the keyboard are skipped.
+pub fn same_hand_rolls(word: &str) -> usize {
+    let hands = word.chars()
+        .filter(|c| c.is_ascii_lowercase())
+        .map(|c| LEFT_HAND_KEYS.contains(c))
+        .collect::<Vec<_>>();
+    hands.windows(2).filter(|pair| pair[0] == pair[1]).count()
+}
+
+/// Among suggestions next to each other in the list which are equally informative (they split
+/// the candidates into groups of the same sizes, and are either both candidates or both not), put
+/// the ones with the fewest [`same_hand_rolls`] first, so they're easier to type. The order is
+/// otherwise unchanged.
+pub fn prefer_ergonomic<S, W>(suggestions: &mut Vec<S>, candidates: &[W])
+    where S: AsRef<str>,
+          W: AsRef<str>,
+{
+    let mut keyed = suggestions.drain(..)
+        .map(|guess| {
+            let mut sizes = split_sizes(guess.as_ref(), candidates);
+            sizes.sort_unstable();
+            let is_candidate = candidates.iter().any(|w| w.as_ref() == guess.as_ref());
+            ((sizes, is_candidate), guess)
+        })
+        .collect::<Vec<_>>();
+    for run in keyed.chunk_by_mut(|(a, _), (b, _)| a == b) {
+        run.sort_by_cached_key(|(_, guess)| same_hand_rolls(guess.as_ref()));
+    }
+    *suggestions = keyed.into_iter().map(|(_, guess)| guess).collect();
+}
+
+/// Pick the guess, out of an arbitrary pool of probe words, which best splits up the candidates.
+///
+/// The probe words don't need to be candidates themselves, but when two probes score the same,
+/// one which could be the answer is preferred. Returns `None` if either list is empty.
+pub fn best_guess_among(guesses: &[&str], candidates: &[&str], metric: SplitMetric)
+    -> Option<String>
+{
+    if candidates.is_empty() {
+        return None;
+    }
+    rank_by_split(guesses, candidates, metric).into_iter().next().map(|(guess, _)| guess)
+}
+
+/// The guess, out of an arbitrary pool, which leaves the fewest candidates on average, with that
+/// expected number. The guess doesn't have to be a candidate. Returns `None` if either list is
+/// empty.
+pub fn best_probe<G, W>(guesses: &[G], candidates: &[W]) -> Option<(String, f64)>
+    where G: AsRef<str>,
+          W: AsRef<str>,
+{
+    if candidates.is_empty() {
+        return None;
+    }
+    rank_by_split(guesses, candidates, SplitMetric::ExpectedRemaining)
+        .into_iter()
+        .next()
+        .map(|(guess, score)| (guess, -score))
+}
+
+/// Parse a row of feedback in the form the user types it: each letter prefixed by `*` for green,
+/// `?` for yellow, or `!` for gray. The row has to have feedback for all `num_letters` letters.
+///
+/// ```
+/// use wordle_solve::*;
+///
+/// let infos = parse_input("*c?a!t", 3).unwrap();
+/// assert_eq!(infos, [Info::Exact('c'), Info::Somewhere('a'), Info::No('t')]);
+///
+/// let mut knowledge = Knowledge::new(3);
+/// knowledge.add_infos(&infos, false).unwrap();
+/// assert!(knowledge.check_word("cpa", false));
+/// assert!(!knowledge.check_word("cat", false));
+/// ```
+pub fn parse_input(inp: &str, num_letters: usize) -> Result<Vec<Info>, SolveError> {
+    let infos = parse_input_partial(inp, num_letters)?;
+    if infos.len() != num_letters {
+        return Err(SolveError::LengthMismatch { expected: num_letters, actual: infos.len() });
+    }
+    Ok(infos)
+}
+
+/// Like [`parse_input`], but the row can stop short, giving feedback for only the first few
+/// letters. The rest are left unconstrained.
+pub fn parse_input_partial(inp: &str, num_letters: usize) -> Result<Vec<Info>, SolveError> {
+    let mut flag = None;
+    let mut infos = vec![];
+    for c in inp.chars() {
+        if infos.len() == num_letters {
+            return Err(SolveError::TooManyLetters);
+        }
+        if c.is_whitespace() {
+            continue;
+        }
+        if flag.is_none() {
+            flag = Some(c);
+            continue;
+        }
+        let info = match flag.unwrap() {
+            '*' => Info::Exact(c),
+            '?' => Info::Somewhere(c),
+            '!' => Info::No(c),
+            other => {
+                return Err(SolveError::UnknownFlag(other));
+            }
+        };
+        infos.push(info);
+        flag = None;
+    }
+    if let Some(extra) = flag {
+        return Err(SolveError::UnprocessedInput(extra));
+    }
+    Ok(infos)
+}
+
+/// Parse a row of feedback pasted from a terminal game, where each letter's tile color is given by
+/// an ANSI escape sequence setting the background: green (42 or 102), yellow (43 or 103), or gray
+/// (40, 47 or 100). Whitespace between tiles is ignored.
+pub fn parse_ansi(inp: &str, num_letters: usize) -> Result<Vec<Info>, SolveError> {
+    let mut color = None;
+    let mut infos = vec![];
+    let mut chars = inp.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            chars.next();
+            let mut params = String::new();
+            for p in chars.by_ref() {
+                if p == 'm' {
+                    break;
+                }
+                params.push(p);
+            }
+            for param in params.split(';') {
+                match param {
+                    "" | "0" | "49" => color = None,
+                    "42" | "102" => color = Some(Info::Exact as fn(char) -> Info),
+                    "43" | "103" => color = Some(Info::Somewhere),
+                    "40" | "47" | "100" => color = Some(Info::No),
+                    _ => (),
+                }
+            }
+        } else if !c.is_whitespace() {
+            let Some(info) = color else {
+                return Err(SolveError::Uncolored(c));
+            };
+            if infos.len() == num_letters {
+                return Err(SolveError::TooManyLetters);
+            }
+            infos.push(info(c.to_ascii_lowercase()));
+        }
+    }
+    if infos.len() != num_letters {
+        return Err(SolveError::LengthMismatch { expected: num_letters, actual: infos.len() });
+    }
+    Ok(infos)
+}
 
-    /// Gray letters
-    No(char),
+/// Parse a crossword-style pattern of known letters, like `c_a_e`: a lowercase letter is known to
+/// be at that position (green), and `_` is unknown. Returns the known positions (counting from
+/// zero) and letters.
+pub fn parse_known_pattern(pattern: &str, num_letters: usize)
+    -> Result<Vec<(usize, char)>, SolveError>
+{
+    let len = pattern.chars().count();
+    if len != num_letters {
+        return Err(SolveError::LengthMismatch { expected: num_letters, actual: len });
+    }
+    let mut known = vec![];
+    for (idx, c) in pattern.chars().enumerate() {
+        match c {
+            '_' => (),
+            c if c.is_lowercase() => known.push((idx, c)),
+            other => return Err(SolveError::UnprocessedInput(other)),
+        }
+    }
+    Ok(known)
 }
 
-/// Represents everything known about the game state.
-#[derive(Debug, Clone)]
-pub struct Knowledge {
-    /// Restrictions on which letters can go in which spaces.
-    restrictions: Vec<Restriction>,
+/// Build a map of letters to how often they occur in the dictionary, normalized by the total
+/// number of letters.
+pub fn letter_frequencies<I, W>(dictionary: I) -> HashMap<char, f64>
+    where I: IntoIterator<Item=W>,
+          W: AsRef<str>,
+{
+    let mut letter_freq = HashMap::<char, f64>::new();
+    for word in dictionary {
+        for c in word.as_ref().chars() {
+            *letter_freq.entry(c).or_insert(0.) += 1.;
+        }
+    }
+    let total_letters = letter_freq.values().sum::<f64>();
+    for v in letter_freq.values_mut() {
+        *v /= total_letters;
+    }
+    letter_freq
+}
 
-    /// Letters that must appear *somewhere* in the word (and how many times).
-    must_have: HashMap<char, usize>,
+/// For each position, a map of letters to how often they occur there in the dictionary, normalized
+/// by the number of words, so each position's frequencies add up to 1. Unlike
+/// [`letter_frequencies`], this tells an S at the start of a word from one at the end.
+pub fn build_position_freq<I, W>(dictionary: I) -> Vec<HashMap<char, f64>>
+    where I: IntoIterator<Item=W>,
+          W: AsRef<str>,
+{
+    let mut position_freq = Vec::<HashMap<char, f64>>::new();
+    let mut num_words = 0;
+    for word in dictionary {
+        num_words += 1;
+        for (i, c) in word.as_ref().chars().enumerate() {
+            if position_freq.len() <= i {
+                position_freq.push(HashMap::new());
+            }
+            *position_freq[i].entry(c).or_insert(0.) += 1.;
+        }
+    }
+    for freq in &mut position_freq {
+        for v in freq.values_mut() {
+            *v /= num_words as f64;
+        }
+    }
+    position_freq
 }
 
-/// A restriction on a letter at a particular position.
-#[derive(Debug, Clone)]
-pub enum Restriction {
-    /// Letter must be exactly the given letter.
-    Exact(char),
+/// Which of the most common letters a set of openers tries, from [`opener_coverage`]. Each list is
+/// most common first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// The common letters which are in at least one of the openers.
+    pub covered: Vec<char>,
 
-    /// Letter must not be any of the given letters.
-    Not(Vec<char>),
+    /// The common letters which aren't in any of the openers.
+    pub missed: Vec<char>,
+
+    /// The vowels which aren't in any of the openers, common or not.
+    pub missed_vowels: Vec<char>,
 }
 
-pub fn best_candidates<I, W>(
-    candidates: I,
-    knowledge: &Knowledge,
-    letter_freq: &HashMap<char, f64>,
-) -> Vec<<W as ToOwned>::Owned>
-    where I: Iterator<Item=W>,
-          W: AsRef<str> + ToOwned,
+impl std::fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |letters: &[char]| letters.iter().map(char::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(f, "covers {} of the {} most common letters: {}", self.covered.len(),
+            self.covered.len() + self.missed.len(), list(&self.covered))?;
+        writeln!(f, "misses: {}", list(&self.missed))?;
+        write!(f, "misses vowels: {}", list(&self.missed_vowels))
+    }
+}
+
+/// Check which of the `top` most common letters (by `letter_freq`) are tried by a set of openers,
+/// for judging a fixed opening.
+pub fn opener_coverage(words: &[&str], letter_freq: &HashMap<char, f64>, top: usize)
+    -> CoverageReport
 {
-    let mut by_letters = candidates
-        .map(|word| {
-            let mut letters = word.as_ref().chars().collect::<Vec<_>>();
-            letters.sort_unstable();
-            letters.dedup();
-            (word, letters.len())
+    let tried = words.iter().flat_map(|word| word.chars()).collect::<BTreeSet<_>>();
+    let mut by_freq = letter_freq.iter().collect::<Vec<_>>();
+    by_freq.sort_by_key(|&(&c, &freq)| (std::cmp::Reverse(NonNan::try_from(freq).unwrap()), c));
+    let (covered, missed) = by_freq.into_iter()
+        .take(top)
+        .map(|(&c, _)| c)
+        .partition(|c| tried.contains(c));
+    let missed_vowels = "aeiou".chars().filter(|c| !tried.contains(c)).collect();
+    CoverageReport { covered, missed, missed_vowels }
+}
+
+/// Format feedback the same way the user types it in: each letter prefixed by `*` for green, `?`
+/// for yellow, and `!` for gray.
+pub fn format_infos(infos: &[Info]) -> String {
+    infos.iter()
+        .map(|info| match info {
+            Info::Exact(c) => format!("*{}", c),
+            Info::Somewhere(c) => format!("?{}", c),
+            Info::No(c) => format!("!{}", c),
         })
-        .collect::<Vec<_>>();
-    by_letters.sort_unstable_by(|(_, c1), (_, c2)| c2.cmp(c1));
-
-    let mut results = vec![];
-
-    // Start with the words with the most unique letters. If that gives less than 10 results, then
-    // continue ranking and adding words with fewer unique letters.
-    let mut by_letters_ref = &mut by_letters[..];
-    while results.len() < 10 {
-        if by_letters_ref.is_empty() {
-            break; // shouldn't happen unless the word is not in the dictionary somehow
-        }
-        let most_letters_count = by_letters_ref[0].1;
-        let len = {
-            // Only look at the words with the most unique letters.
-            let most_unique_letters = by_letters_ref.split_mut(|(_, count)| *count < most_letters_count).next().unwrap();
-            if most_unique_letters.len() != 1 {
-                // Sort the words score, according to letter frequency.
-                most_unique_letters.sort_by_cached_key::<NonNan, _>(|(word, _)| {
-                    word.as_ref().chars()
-                        .map(|c| {
-                            // Letters we already have knowledge about count for zero.
-                            if knowledge.must_have.iter().any(|(&x, _)| x == c)
-                                || knowledge.restrictions.iter().any(|r| {
-                                    match r {
-                                        Restriction::Not(v) => v.iter().any(|&x| x == c),
-                                        Restriction::Exact(x) => *x == c,
-                                    }
-                                })
-                            {
-                                0.
-                            } else {
-                                // Otherwise, add up the frequency of letters in the dictionary.
-                                // Negative, so they are sorted with highest score first.
-                                -letter_freq[&c]
-                            }
-                        })
-                        .sum::<f64>()
-                        .try_into() // into NonNan
-                        .unwrap()
-                });
-            }
-            results.extend(
-                most_unique_letters
-                    .iter()
-                    .map(|(word, _)| word.to_owned())
-            );
-            most_unique_letters.len()
-        };
+        .collect()
+}
 
-        // Subsequent loop iterations will skip over these words and begin considering words with
-        // fewer unique letters.
-        by_letters_ref = &mut by_letters_ref[len .. ];
-        if by_letters_ref.is_empty() {
-            break;
+/// Render rows of feedback as a grid of colored squares, one row per line, like the game's share
+/// text.
+pub fn render_emoji_grid(guesses: &[Vec<Info>]) -> String {
+    let mut out = String::new();
+    for infos in guesses {
+        for info in infos {
+            out.push(match info {
+                Info::Exact(_) => '\u{1f7e9}',     // green square
+                Info::Somewhere(_) => '\u{1f7e8}', // yellow square
+                Info::No(_) => '\u{2b1b}',         // black square
+            });
         }
+        out.push('\n');
+    }
+    out
+}
+
+/// Read one row of a pasted share grid (like `🟩🟨⬛⬛🟩`), taking the letters from the word that
+/// was guessed. Gray can be either a black or a white square, depending on the game's theme.
+/// Whitespace and variation selectors (which some platforms add after each square) are ignored.
+pub fn parse_emoji_guess(guess: &str, emoji_row: &str) -> Result<Vec<Info>, SolveError> {
+    let tiles = emoji_row.chars()
+        .filter(|&c| !c.is_whitespace() && !matches!(c, '\u{fe0e}' | '\u{fe0f}'))
+        .collect::<Vec<_>>();
+    let letters = guess.chars().collect::<Vec<_>>();
+    if tiles.len() != letters.len() {
+        return Err(SolveError::LengthMismatch { expected: letters.len(), actual: tiles.len() });
+    }
+    letters.into_iter()
+        .zip(tiles)
+        .map(|(c, tile)| match tile {
+            '\u{1f7e9}' => Ok(Info::Exact(c)),            // green square
+            '\u{1f7e8}' => Ok(Info::Somewhere(c)),        // yellow square
+            '\u{2b1b}' | '\u{2b1c}' => Ok(Info::No(c)),   // black or white square
+            _ => Err(SolveError::UnknownTile(tile)),
+        })
+        .collect()
+}
+
+/// Like [`parse_emoji_guess`], but also checks that the guess has the right number of letters.
+pub fn parse_emoji_input_with_letters(letters: &str, tiles: &str, num_letters: usize)
+    -> Result<Vec<Info>, SolveError>
+{
+    let letters = letters.trim();
+    let actual = letters.chars().count();
+    if actual != num_letters {
+        return Err(SolveError::LengthMismatch { expected: num_letters, actual });
     }
-    results
+    parse_emoji_guess(letters, tiles)
+}
+
+/// The answer for the given day's puzzle, from a list of answers in the order they're used. None
+/// if the list doesn't go that far.
+pub fn answer_for_day<W: AsRef<str>>(answers: &[W], day: u32) -> Option<&str> {
+    answers.get(day as usize).map(|answer| answer.as_ref())
 }
 
+/// Render the full share text for a game: a header like `Wordle 123 4/6` (or `X/6` if the last
+/// row isn't all green), a blank line, and the emoji grid. Rows past `max` aren't shown and count
+/// as a failure.
+pub fn render_share(day: Option<u32>, guesses: &[Vec<Info>], max: usize) -> String {
+    let solved = guesses.len() <= max
+        && guesses.last()
+            .is_some_and(|infos| infos.iter().all(|info| matches!(info, Info::Exact(_))));
+    let score = if solved { guesses.len().to_string() } else { "X".to_owned() };
+    let mut out = match day {
+        Some(day) => format!("Wordle {} {}/{}\n\n", day, score, max),
+        None => format!("Wordle {}/{}\n\n", score, max),
+    };
+    out += &render_emoji_grid(&guesses[.. guesses.len().min(max)]);
+    out
+}
+
+/// The feedback the game would give for a guess, when the answer is `word`.
 pub fn check_guess(word: &str, guess: &str) -> Vec<Info> {
-    let mut infos = vec![];
+    // First pass: greens, and which letters of the word are left over for yellows.
+    let mut infos = Vec::with_capacity(guess.len());
+    let mut unmatched = Vec::with_capacity(word.len());
     for (gc, wc) in guess.chars().zip(word.chars()) {
-        let info = if wc == gc {
-            Info::Exact(gc)
-        } else if word.contains(gc) {
-            // How many are in the actual word?
-            let count = word.chars()
-                .filter(|&c| c == gc)
-                .count();
-            // How many are in the right position? These get green tiles first.
-            let matched = word.chars()
-                .zip(guess.chars())
-                .filter(|(w, g)| w == g && *w == gc)
-                .count();
-            // How many yellow tiles have we assigned elsewhere?
-            let elsewhere = infos.iter()
-                .filter(|i| matches!(i, Info::Somewhere(c) if *c == gc))
-                .count();
-            if count > matched + elsewhere {
-                // There's more to be found; give a yellow tile.
-                Info::Somewhere(gc)
-            } else {
-                // Enough non-gray tiles have been assigned already.
-                Info::No(gc)
+        if gc == wc {
+            infos.push(Info::Exact(gc));
+        } else {
+            infos.push(Info::No(gc));
+            unmatched.push(wc);
+        }
+    }
+    // Second pass: each leftover letter gives a yellow to the first non-green copy in the guess;
+    // any more copies are gray.
+    for info in &mut infos {
+        if let Info::No(gc) = *info {
+            if let Some(pos) = unmatched.iter().position(|&c| c == gc) {
+                unmatched.swap_remove(pos);
+                *info = Info::Somewhere(gc);
             }
+        }
+    }
+    infos
+}
+
+/// Have the solver guess the word, returning each guess and how many candidates were left after it.
+/// Guesses come from `guess_pool` if given (which should include the candidates), or else from
+/// the candidates themselves. Either way, they have to fit what's known so far, except in normal
+/// mode, where any word can be guessed even if it can't be the answer (see
+/// [`best_candidates_unrestricted`]); the strategy isn't used then. If the word can't be found
+/// (e.g. it isn't a candidate), the last entry is an empty guess.
+pub fn guess_word(
+    word: &str,
+    candidates: BTreeSet<String>,
+    guess_pool: Option<&BTreeSet<String>>,
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+    normal_mode: bool,
+) -> Vec<(String, usize)> {
+    guess_word_with_openers(word, &[], candidates, guess_pool, letter_freq, strategy, normal_mode,
+        None)
+}
+
+/// Like [`guess_word`], but the first guesses are always the given openers, in order, whatever
+/// feedback they get (unless one of them is the word). The solver takes over after them. If
+/// `max_guesses` is given, it gives up after that many guesses, adding an empty guess at the end
+/// like when the word can't be found.
+#[allow(clippy::too_many_arguments)]
+pub fn guess_word_with_openers(
+    word: &str,
+    openers: &[String],
+    candidates: BTreeSet<String>,
+    guess_pool: Option<&BTreeSet<String>>,
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+    normal_mode: bool,
+    max_guesses: Option<usize>,
+) -> Vec<(String, usize)> {
+    solve_word(word, openers, candidates, guess_pool, letter_freq, strategy, normal_mode,
+        max_guesses, None)
+        .unwrap_or_else(|e| panic!("ERROR on {}: {}", word, e))
+}
+
+/// Like [`guess_word_with_openers`], but checks the solver's work along the way, returning an
+/// error if the feedback for a guess doesn't fit what's already known, or rules the word out.
+/// Neither should ever happen.
+#[allow(clippy::too_many_arguments)]
+pub fn guess_word_checked(
+    word: &str,
+    openers: &[String],
+    candidates: BTreeSet<String>,
+    guess_pool: Option<&BTreeSet<String>>,
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+    normal_mode: bool,
+    max_guesses: Option<usize>,
+) -> Result<Vec<(String, usize)>, SolveError> {
+    solve_word(word, openers, candidates, guess_pool, letter_freq, strategy, normal_mode,
+        max_guesses, None)
+}
+
+/// [`guess_word_with_openers`], ranking guesses with the help of the table, if there is one.
+#[allow(clippy::too_many_arguments)]
+fn solve_word(
+    word: &str,
+    openers: &[String],
+    mut candidates: BTreeSet<String>,
+    guess_pool: Option<&BTreeSet<String>>,
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+    normal_mode: bool,
+    max_guesses: Option<usize>,
+    table: Option<&PatternTable>,
+) -> Result<Vec<(String, usize)>, SolveError> {
+    let mut guesses = vec![];
+    let mut knowledge = Knowledge::new(word.chars().count());
+    let all_guesses = if normal_mode {
+        guess_pool.unwrap_or(&candidates).iter().cloned().collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+    let mut guessable = guess_pool.filter(|_| !normal_mode).cloned();
+
+    loop {
+        if max_guesses.is_some_and(|max| guesses.len() >= max) {
+            guesses.push((String::new(), 0));
+            return Ok(guesses);
+        }
+        let candidate_list = candidates.iter().collect::<Vec<_>>();
+        let best_guesses = if let Some(opener) = openers.get(guesses.len()) {
+            vec![opener.clone()]
+        } else if normal_mode {
+            best_candidates_unrestricted(&all_guesses, &candidate_list, &knowledge, letter_freq)
         } else {
-            Info::No(gc)
+            let guess_list = guessable.as_ref().map(|words| words.iter().collect::<Vec<_>>());
+            strategy.rank_guesses_with_table(guess_list.as_deref().unwrap_or(&candidate_list),
+                &candidate_list, &knowledge, letter_freq, table)
         };
-        infos.push(info);
+        if best_guesses.is_empty() {
+            guesses.push((String::new(), 0));
+            return Ok(guesses);
+        }
+        let guess = best_guesses[0].clone();
+        if guess == word {
+            guesses.push((guess, 1));
+            return Ok(guesses);
+        }
+
+        let infos = check_guess(word, &guess);
+        knowledge.add_infos(&infos, false)?;
+        if !knowledge.check_word(word, false) {
+            let guesses = guesses.into_iter().map(|(guess, _)| guess).chain([guess]).collect();
+            return Err(SolveError::RuledOut { word: word.to_owned(), guesses });
+        }
+
+        candidates.retain(|word| knowledge.check_word(word, false));
+        if let Some(guessable) = &mut guessable {
+            guessable.retain(|word| knowledge.check_word(word, false));
+        }
+        guesses.push((guess, candidates.len()));
     }
-    infos
+}
+
+/// Play host like Absurdle: rather than having an answer in mind, give whichever feedback for the
+/// guess keeps the most candidates alive. Returns that feedback, and the candidates which would
+/// have given it. Ties go to the feedback with the fewest greens and yellows, counting from the
+/// start of the word.
+pub fn adversarial_feedback(guess: &str, candidates: &BTreeSet<String>)
+    -> (Vec<Info>, BTreeSet<String>)
+{
+    let mut buckets = HashMap::<Vec<Info>, BTreeSet<String>>::new();
+    for word in candidates {
+        buckets.entry(check_guess(word, guess)).or_default().insert(word.clone());
+    }
+    let colors = |infos: &[Info]| {
+        infos.iter()
+            .map(|info| match info {
+                Info::Exact(_) => 2,
+                Info::Somewhere(_) => 1,
+                Info::No(_) => 0,
+            })
+            .collect::<Vec<u8>>()
+    };
+    buckets.into_iter()
+        .max_by_key(|(infos, words)| (words.len(), std::cmp::Reverse(colors(infos))))
+        .unwrap_or_else(|| (guess.chars().map(Info::No).collect(), BTreeSet::new()))
+}
+
+/// Have the solver play against [`adversarial_feedback`], which keeps the answer from being
+/// pinned down for as long as it can. This is the most guesses the solver could ever need for
+/// these candidates. Returns the guesses in the same form as [`guess_word`]; the last one is
+/// the word the host was finally forced to accept.
+pub fn guess_adversarial(
+    mut candidates: BTreeSet<String>,
+    openers: &[String],
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+    max_guesses: Option<usize>,
+) -> Vec<(String, usize)> {
+    let mut guesses = vec![];
+    let Some(num_letters) = candidates.first().map(|word| word.chars().count()) else {
+        return guesses;
+    };
+    let mut knowledge = Knowledge::new(num_letters);
+    loop {
+        if max_guesses.is_some_and(|max| guesses.len() >= max) {
+            guesses.push((String::new(), 0));
+            return guesses;
+        }
+        let guess = match openers.get(guesses.len()) {
+            Some(opener) => opener.clone(),
+            None => {
+                let candidate_list = candidates.iter().collect::<Vec<_>>();
+                match strategy.rank(&candidate_list, &knowledge, letter_freq).into_iter().next() {
+                    Some(guess) => guess,
+                    None => {
+                        guesses.push((String::new(), 0));
+                        return guesses;
+                    }
+                }
+            }
+        };
+        let (infos, remaining) = adversarial_feedback(&guess, &candidates);
+        if infos.iter().all(|info| matches!(info, Info::Exact(_))) {
+            guesses.push((guess, 1));
+            return guesses;
+        }
+        if let Err(e) = knowledge.add_infos(&infos, false) {
+            panic!("ERROR in adversarial game (guessing {}): {}", guess, e);
+        }
+        candidates = remaining;
+        guesses.push((guess, candidates.len()));
+    }
+}
+
+/// How the solver did on one word, from [`check_all_words_parallel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordResult {
+    pub word: String,
+    pub num_guesses: usize,
+    /// How many candidates there were to start with.
+    pub dict_size: usize,
+    /// As returned by [`guess_word`].
+    pub guesses: Vec<(String, usize)>,
+}
+
+impl WordResult {
+    /// Whether the word was found; if not, the last guess is empty.
+    pub fn solved(&self) -> bool {
+        self.guesses.last().is_some_and(|(guess, _)| !guess.is_empty())
+    }
+}
+
+/// Have the solver guess every word in the dictionary with [`Strategy::Frequency`], spread over
+/// all the CPUs. The results are in dictionary order.
+pub fn check_all_words_parallel(dictionary: &BTreeSet<String>, letter_freq: &HashMap<char, f64>)
+    -> Vec<WordResult>
+{
+    let words = dictionary.iter().collect::<Vec<_>>();
+    solve_words_parallel(&words, dictionary, None, &[], letter_freq, &Strategy::Frequency, None)
+}
+
+/// Have the solver guess each of the words, as with [`guess_word_with_openers`], spread over all
+/// the CPUs. The results are in the same order as the words. With [`Strategy::Entropy`], every
+/// game starts by ranking all the guesses against all the candidates, so the feedback for all of
+/// them is worked out once up front, in a [`PatternTable`], rather than once per game.
+pub fn solve_words_parallel(
+    words: &[&String],
+    candidates: &BTreeSet<String>,
+    guess_pool: Option<&BTreeSet<String>>,
+    openers: &[String],
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+    max_guesses: Option<usize>,
+) -> Vec<WordResult> {
+    let table = (*strategy == Strategy::Entropy && words.len() > 1)
+        .then(|| {
+            let guesses = guess_pool.unwrap_or(candidates).iter().collect::<Vec<_>>();
+            PatternTable::new(&guesses, &candidates.iter().collect::<Vec<_>>()).ok()
+        })
+        .flatten();
+    words.par_iter()
+        .map(|&word| {
+            let guesses = solve_word(word, openers, candidates.clone(), guess_pool, letter_freq,
+                strategy, false, max_guesses, table.as_ref())
+                .unwrap_or_else(|e| panic!("ERROR on {}: {}", word, e));
+            WordResult {
+                word: word.clone(),
+                num_guesses: guesses.len(),
+                dict_size: candidates.len(),
+                guesses,
+            }
+        })
+        .collect()
 }
 
 impl Knowledge {
+    /// Nothing known yet, about a word with the given number of letters.
     pub fn new(num_letters: usize) -> Self {
         Self {
-            restrictions: vec![Restriction::Not(vec![]); num_letters],
+            restrictions: vec![Restriction::Not(LetterSet::new()); num_letters],
             must_have: HashMap::new(),
+            max_have: HashMap::new(),
+            gray_means_absent: true,
+            validity: is_lowercase_word,
         }
     }
 
-    fn add_info(&mut self, idx: usize, info: &Info, verbose: bool) -> Result<(), String> {
+    /// Set which words are valid at all. By default, that's words made of only the lowercase
+    /// letters a to z, but other puzzles can use other alphabets or rules (e.g. equations which
+    /// have to balance).
+    pub fn with_validity(mut self, validity: fn(&str) -> bool) -> Self {
+        self.validity = validity;
+        self
+    }
+
+    /// Set whether gray tiles exclude a letter from the whole word (the default), or only from
+    /// the position where the gray tile was.
+    pub fn with_gray_means_absent(mut self, gray_means_absent: bool) -> Self {
+        self.gray_means_absent = gray_means_absent;
+        self
+    }
+
+    fn add_info(&mut self, idx: usize, info: &Info) -> Result<(), KnowledgeError> {
         match info {
             Info::Exact(c) => {
                 if let Restriction::Exact(x) = &self.restrictions[idx] {
                     if x != c {
-                        return Err(format!("you already said that letter {} is {:?}", idx, x));
+                        return Err(KnowledgeError::ConflictingExact {
+                            position: idx,
+                            existing: *x,
+                            new: *c,
+                        });
                     }
                 }
                 self.restrictions[idx] = Restriction::Exact(*c);
             }
             Info::Somewhere(c) => {
                 match &mut self.restrictions[idx] {
-                    Restriction::Exact(x) => {
-                        return Err(format!("you already said that letter {} is {:?}", idx, x));
+                    Restriction::Exact(x) if x == c => {
+                        return Err(KnowledgeError::ExactPositionIsYellow {
+                            position: idx,
+                            letter: *x,
+                        });
                     }
+                    // A guess which doesn't keep a known letter in place (as in normal mode) can
+                    // still get a yellow there for a different letter.
+                    Restriction::Exact(_) => (),
                     Restriction::Not(list) => {
-                        list.push(*c);
+                        list.insert(*c);
                     }
                 }
                 *self.must_have.entry(*c).or_insert(0) += 1;
             }
             Info::No(c) => {
-                let mut add = true;
-                for r in &mut self.restrictions {
-                    if let Restriction::Not(list) = r {
-                        if list.iter().any(|x| x == c) {
-                            if verbose {
-                                eprintln!("not adding restriction against {}; already have one somewhere", c);
-                            }
-                            add = false;
-                            break;
-                        }
-                    }
+                // A gray tile always rules the letter out here. Whether it's ruled out of the
+                // rest of the word depends on the rest of the row, so add_infos does that.
+                if let Restriction::Not(list) = &mut self.restrictions[idx] {
+                    list.insert(*c);
                 }
-                if add {
-                    if verbose {
-                        eprintln!("adding restriction against {}", c);
-                    }
-                    for r in &mut self.restrictions {
-                        if let Restriction::Not(list) = r {
-                            if !list.iter().any(|x| x == c) {
-                                list.push(*c);
-                            }
-                        }
-                    }
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark any position where all the candidates have the same letter as known to be that letter,
+    /// as though it had been green. Returns the positions (and letters) newly marked this way.
+    pub fn infer_forced<W: AsRef<str>>(&mut self, candidates: &[W]) -> Vec<(usize, char)> {
+        let mut forced = vec![];
+        let Some((first, rest)) = candidates.split_first() else {
+            return forced;
+        };
+        for (idx, c) in first.as_ref().chars().enumerate() {
+            if !matches!(self.restrictions.get(idx), Some(Restriction::Not(_))) {
+                continue;
+            }
+            if rest.iter().all(|word| word.as_ref().chars().nth(idx) == Some(c)) {
+                self.restrictions[idx] = Restriction::Exact(c);
+                forced.push((idx, c));
+            }
+        }
+        forced
+    }
+
+    /// Mark a position (counting from zero) as known to be the given letter, as though it had been
+    /// green. Fails if the position is out of range or already known to be a different letter.
+    pub fn require_at(&mut self, idx: usize, c: char) -> Result<(), KnowledgeError> {
+        match self.restrictions.get(idx) {
+            None => Err(KnowledgeError::PositionOutOfRange {
+                position: idx,
+                num_letters: self.restrictions.len(),
+            }),
+            Some(Restriction::Exact(x)) if *x != c => {
+                Err(KnowledgeError::ConflictingExact { position: idx, existing: *x, new: c })
+            }
+            Some(_) => {
+                self.restrictions[idx] = Restriction::Exact(c);
+                Ok(())
+            }
+        }
+    }
+
+    /// Mark a letter as known to be somewhere in the word, as though it had been yellow, without
+    /// saying where.
+    pub fn require_somewhere(&mut self, c: char) {
+        let count = self.must_have.entry(c).or_insert(0);
+        *count = (*count).max(1);
+    }
+
+    /// Rule a letter out of the given positions (counting from zero), without saying anything
+    /// about whether it's elsewhere in the word. This is for things deduced some other way than
+    /// from a guess's feedback. Nothing is changed if any of the positions is out of range or
+    /// already known to be that letter.
+    pub fn forbid_positions(&mut self, c: char, positions: &[usize]) -> Result<(), KnowledgeError> {
+        for &idx in positions {
+            match self.restrictions.get(idx) {
+                None => {
+                    return Err(KnowledgeError::PositionOutOfRange {
+                        position: idx,
+                        num_letters: self.restrictions.len(),
+                    });
+                }
+                Some(Restriction::Exact(x)) if *x == c => {
+                    return Err(KnowledgeError::ExactPositionIsYellow { position: idx, letter: *x });
                 }
+                Some(_) => (),
+            }
+        }
+        for &idx in positions {
+            if let Restriction::Not(list) = &mut self.restrictions[idx] {
+                list.insert(c);
             }
         }
         Ok(())
     }
 
-    pub fn add_infos(&mut self, infos: &[Info], verbose: bool) -> Result<(), String> {
+    /// Take into account the feedback for a guess. Fails if it contradicts what's already known,
+    /// in which case the knowledge may be partly updated.
+    pub fn add_infos(&mut self, infos: &[Info], verbose: bool) -> Result<(), KnowledgeError> {
+        if infos.len() > self.restrictions.len() {
+            return Err(KnowledgeError::LengthMismatch {
+                expected: self.restrictions.len(),
+                actual: infos.len(),
+            });
+        }
         let mut k2 = self.clone();
         let mut must = HashMap::new();
+        let mut grays = vec![];
 
         for (i, info) in infos.iter().enumerate() {
-            k2.add_info(i, info, verbose)?;
+            k2.add_info(i, info)?;
             match info {
                 Info::Somewhere(c) | Info::Exact(c) => {
                     *must.entry(c).or_insert(0) += 1;
                 }
-                Info::No(_) => (),
+                Info::No(c) => grays.push(*c),
+            }
+        }
+
+        if self.gray_means_absent {
+            for c in grays {
+                if let Some(&num) = must.get(&c) {
+                    // A gray copy of a letter which is also colored means there are no more
+                    // copies than the colored ones.
+                    let max = k2.max_have.entry(c).or_insert(num);
+                    *max = (*max).min(num);
+                } else if self.must_have.contains_key(&c)
+                    || self.restrictions.iter().any(|r| matches!(r, Restriction::Exact(x) if *x == c))
+                {
+                    if verbose {
+                        eprintln!("not adding restriction against {} everywhere; it's in the word", c);
+                    }
+                } else {
+                    if verbose {
+                        eprintln!("adding restriction against {}", c);
+                    }
+                    for r in &mut k2.restrictions {
+                        if let Restriction::Not(list) = r {
+                            list.insert(c);
+                        }
+                    }
+                }
             }
         }
 
@@ -230,42 +1789,274 @@ impl Knowledge {
         Ok(())
     }
 
-    pub fn check_word(&self, word: &str, verbose: bool) -> bool {
-        if word.chars().count() != self.restrictions.len() {
-            return false;
+    /// Take into account the colors seen for a guess, given separately from the word.
+    pub fn apply_guess(&mut self, word: &str, pattern: &[Pattern]) -> Result<(), KnowledgeError> {
+        let num_letters = word.chars().count();
+        if num_letters != pattern.len() {
+            return Err(KnowledgeError::LengthMismatch {
+                expected: num_letters,
+                actual: pattern.len(),
+            });
         }
+        let infos = word.chars()
+            .zip(pattern)
+            .map(|(c, color)| color.info(c))
+            .collect::<Vec<_>>();
+        self.add_infos(&infos, false)
+    }
+
+    /// Write what's known as a single row of feedback, the same way the user types it in (like
+    /// `*a?b!c?d*e`), so it can be saved and picked up again with
+    /// [`from_pattern_string`](Self::from_pattern_string). Each position gets its green letter, or
+    /// else the one letter it's known not to be. That can't say everything after a few guesses
+    /// (like two different yellows in one place), so this returns None unless reading the row
+    /// back in gives exactly the same knowledge.
+    pub fn to_pattern_string(&self) -> Option<String> {
+        let mut grays_written = LetterSet::new();
+        let infos = self.restrictions.iter()
+            .map(|r| match r {
+                Restriction::Exact(c) => Some(Info::Exact(*c)),
+                Restriction::Not(list) => {
+                    let mut yellows = list.iter().filter(|c| self.must_have.contains_key(c));
+                    if let Some(c) = yellows.next() {
+                        return yellows.next().is_none().then_some(Info::Somewhere(c));
+                    }
+                    let c = list.iter()
+                        .find(|&c| !grays_written.contains(c))
+                        .or_else(|| list.iter().next())?;
+                    grays_written.insert(c);
+                    Some(Info::No(c))
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
 
-        for (i, (c, r)) in word.chars().zip(self.restrictions.iter()).enumerate() {
-            if !c.is_ascii_lowercase() {
-                return false;
+        let mut roundtrip = Knowledge {
+            restrictions: vec![Restriction::Not(LetterSet::new()); self.restrictions.len()],
+            must_have: HashMap::new(),
+            max_have: HashMap::new(),
+            ..self.clone()
+        };
+        roundtrip.add_infos(&infos, false).ok()?;
+        let same = roundtrip.restrictions == self.restrictions
+            && roundtrip.must_have == self.must_have
+            && roundtrip.max_have == self.max_have;
+        same.then(|| format_infos(&infos))
+    }
+
+    /// Read back knowledge saved by [`to_pattern_string`](Self::to_pattern_string): a single row
+    /// of feedback, as for [`parse_input`].
+    pub fn from_pattern_string(s: &str, num_letters: usize) -> Result<Knowledge, SolveError> {
+        let infos = parse_input(s, num_letters)?;
+        let mut knowledge = Knowledge::new(num_letters);
+        knowledge.add_infos(&infos, false)?;
+        Ok(knowledge)
+    }
+
+    /// Work out what applying the feedback would add, without actually applying it.
+    pub fn diff(&self, infos: &[Info]) -> Result<KnowledgeDiff, KnowledgeError> {
+        let mut after = self.clone();
+        after.add_infos(infos, false)?;
+
+        let mut diff = KnowledgeDiff::default();
+        for (i, (old, new)) in self.restrictions.iter().zip(&after.restrictions).enumerate() {
+            match (old, new) {
+                (Restriction::Not(_), Restriction::Exact(c)) => diff.new_exact.push((i, *c)),
+                (Restriction::Not(old_list), Restriction::Not(new_list)) => {
+                    diff.new_excluded.extend(new_list.iter()
+                        .filter(|&c| !old_list.contains(c))
+                        .map(|c| (i, c)));
+                }
+                _ => (),
             }
+        }
+        for (&c, &count) in &after.must_have {
+            if self.must_have.get(&c).is_none_or(|&old| old < count) {
+                diff.raised_counts.push((c, count));
+            }
+        }
+        diff.new_excluded.sort_unstable();
+        diff.raised_counts.sort_unstable();
+        Ok(diff)
+    }
 
-            let matches = match r {
-                Restriction::Exact(letter) => c == *letter,
-                Restriction::Not(letters) => letters.iter().all(|&l| l != c),
-            };
-            if !matches {
+    /// How many letters the word has.
+    pub fn num_letters(&self) -> usize {
+        self.restrictions.len()
+    }
+
+    /// Whether the word could still be the answer.
+    pub fn check_word(&self, word: &str, verbose: bool) -> bool {
+        match self.check_word_explain(word) {
+            Ok(()) => {
                 if verbose {
-                    eprintln!("{}: {} violates {:?} at {}", word, c, r, i);
+                    eprintln!("{}: matches", word);
                 }
-                return false;
+                true
             }
+            Err(reason) => {
+                if verbose {
+                    eprintln!("{}: {}", word, reason);
+                }
+                false
+            }
+        }
+    }
+
+    /// Like [`check_word`](Self::check_word), but if the word can't be the answer, say why.
+    pub fn check_word_explain(&self, word: &str) -> Result<(), Rejection> {
+        let len = word.chars().count();
+        if len != self.restrictions.len() {
+            return Err(Rejection::WrongLength { expected: self.restrictions.len(), actual: len });
         }
 
-        for (&c, &count) in &self.must_have {
-            if word.chars().filter(|&x| x == c).count() < count {
-                if verbose {
-                    eprintln!("{}: lacks required letter {} ({} times)", word, c, count);
+        if !(self.validity)(word) {
+            return Err(Rejection::Invalid);
+        }
+
+        for (idx, (c, r)) in word.chars().zip(self.restrictions.iter()).enumerate() {
+            match r {
+                Restriction::Exact(letter) if c != *letter => {
+                    return Err(Rejection::NotGreen { idx, letter: c, green: *letter });
                 }
-                return false;
+                Restriction::Not(letters) if letters.contains(c) => {
+                    return Err(Rejection::Excluded { idx, letter: c });
+                }
+                _ => (),
+            }
+        }
+
+        for (&c, &count) in &self.must_have {
+            let have = word.chars().filter(|&x| x == c).count();
+            if have < count {
+                return Err(Rejection::Missing { letter: c, need: count, have });
+            }
+        }
+
+        for (&c, &max) in &self.max_have {
+            let have = word.chars().filter(|&x| x == c).count();
+            if have > max {
+                return Err(Rejection::TooMany { letter: c, max, have });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A crossword-style summary of what's known, like `_ o _ _ t (also: r)`: green letters in
+    /// place, underscores elsewhere, and then any letters known to be present that haven't been
+    /// placed yet.
+    pub fn letters_pattern(&self) -> String {
+        let pattern = self.restrictions.iter()
+            .map(|r| match r {
+                Restriction::Exact(c) => c.to_string(),
+                Restriction::Not(_) => "_".to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut present = self.must_have.keys()
+            .filter(|&&c| self.key_status(c) == KeyStatus::Present)
+            .collect::<Vec<_>>();
+        present.sort_unstable();
+        if present.is_empty() {
+            pattern
+        } else {
+            let present = present.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", ");
+            format!("{} (also: {})", pattern, present)
+        }
+    }
+
+    /// Count how many of the words fit what's known, but stop counting at `cap`. Useful when only
+    /// "are there at least this many" matters, like "100+ candidates" or "any at all".
+    pub fn filter_count_capped<I, W>(&self, words: I, cap: usize) -> usize
+        where I: IntoIterator<Item=W>,
+              W: AsRef<str>,
+    {
+        words.into_iter()
+            .filter(|word| self.check_word(word.as_ref(), false))
+            .take(cap)
+            .count()
+    }
+
+    /// Classify what is known about a letter, like the keyboard in the game UI.
+    pub fn key_status(&self, c: char) -> KeyStatus {
+        if self.restrictions.iter().any(|r| matches!(r, Restriction::Exact(x) if *x == c)) {
+            return KeyStatus::Placed;
+        }
+        if self.must_have.contains_key(&c) {
+            return KeyStatus::Present;
+        }
+        let mut nots = self.restrictions.iter()
+            .filter_map(|r| match r {
+                Restriction::Not(list) => Some(list),
+                Restriction::Exact(_) => None,
+            })
+            .peekable();
+        if nots.peek().is_some() && nots.all(|list| list.contains(c)) {
+            KeyStatus::Absent
+        } else {
+            KeyStatus::Unknown
+        }
+    }
+}
+
+/// Render a QWERTY keyboard with each letter colored by its [`KeyStatus`], using ANSI escapes.
+pub fn render_keyboard(knowledge: &Knowledge) -> String {
+    let mut out = String::new();
+    for (indent, row) in [(0, "qwertyuiop"), (1, "asdfghjkl"), (3, "zxcvbnm")] {
+        out.extend(std::iter::repeat_n(' ', indent));
+        for c in row.chars() {
+            let color = match knowledge.key_status(c) {
+                KeyStatus::Placed => "\x1b[30;42m",
+                KeyStatus::Present => "\x1b[30;43m",
+                KeyStatus::Absent => "\x1b[37;100m",
+                KeyStatus::Unknown => "",
+            };
+            if color.is_empty() {
+                out.push_str(&format!(" {} ", c));
+            } else {
+                out.push_str(&format!("{} {} \x1b[0m", color, c));
             }
         }
+        out.push('\n');
+    }
+    out
+}
+
+/// Show a guess the way the game does: each letter on a green, yellow or gray background (using
+/// ANSI escapes, in the same colors as [`render_keyboard`]) according to its feedback.
+pub fn format_guess_colored(guess: &str, infos: &[Info]) -> String {
+    guess.chars().zip(infos).map(|(c, info)| colored_tile(c, info)).collect()
+}
+
+/// Like [`format_guess_colored`], with the letters taken from the feedback itself.
+pub fn render_infos_colored(infos: &[Info]) -> String {
+    infos.iter()
+        .map(|info| match info {
+            Info::Exact(c) | Info::Somewhere(c) | Info::No(c) => colored_tile(*c, info),
+        })
+        .collect()
+}
 
-        if verbose {
-            eprintln!("{}: matches", word);
+/// Show a word with the letters already known to be in the right place on a green background
+/// (using ANSI escapes, in the same green as [`format_guess_colored`]), and the rest plain.
+pub fn highlight_known_greens(word: &str, knowledge: &Knowledge) -> String {
+    let mut out = String::new();
+    for (c, r) in word.chars().zip(&knowledge.restrictions) {
+        match r {
+            Restriction::Exact(x) if *x == c => out.push_str(&format!("\x1b[30;42m{}\x1b[0m", c)),
+            _ => out.push(c),
         }
-        true
     }
+    out
+}
+
+fn colored_tile(c: char, info: &Info) -> String {
+    let color = match info {
+        Info::Exact(_) => "\x1b[30;42m",
+        Info::Somewhere(_) => "\x1b[30;43m",
+        Info::No(_) => "\x1b[37;100m",
+    };
+    format!("{} {} \x1b[0m", color, c)
 }
 
 #[derive(PartialEq, PartialOrd)]
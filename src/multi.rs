@@ -0,0 +1,106 @@
+use crate::*;
+
+/// Above this many candidates over all the unsolved boards, [`MultiBoard::best_guess`] only
+/// scores a shortlist of guesses picked by letter frequency, instead of the whole dictionary.
+pub const MULTI_BOARD_SHORTLIST_ABOVE: usize = 1000;
+
+/// Several games played at once with the same guesses, like Dordle (two boards) or Quordle
+/// (four). Each board has its own knowledge and candidates.
+#[derive(Debug, Clone)]
+pub struct MultiBoard {
+    pub boards: Vec<(Knowledge, BTreeSet<String>)>,
+    /// Which boards have had an all-green row.
+    solved: Vec<bool>,
+}
+
+impl MultiBoard {
+    /// Start `num_boards` boards, each with the same knowledge and candidates.
+    pub fn new(num_boards: usize, knowledge: &Knowledge, candidates: &BTreeSet<String>) -> Self {
+        Self {
+            boards: vec![(knowledge.clone(), candidates.clone()); num_boards],
+            solved: vec![false; num_boards],
+        }
+    }
+
+    /// Take into account the feedback one board gave for a guess. Fails if it contradicts what's
+    /// already known about that board, in which case nothing is changed.
+    pub fn apply(&mut self, board: usize, infos: &[Info]) -> Result<(), SolveError> {
+        let (knowledge, candidates) = &mut self.boards[board];
+        let mut updated = knowledge.clone();
+        updated.add_infos(infos, false)?;
+        *knowledge = updated;
+        candidates.retain(|word| knowledge.check_word(word, false));
+        if infos.iter().all(|info| matches!(info, Info::Exact(_))) {
+            self.solved[board] = true;
+        }
+        Ok(())
+    }
+
+    /// Whether the board has had an all-green row.
+    pub fn is_solved(&self, board: usize) -> bool {
+        self.solved[board]
+    }
+
+    /// Whether every board has had an all-green row.
+    pub fn all_solved(&self) -> bool {
+        self.solved.iter().all(|&solved| solved)
+    }
+
+    /// The boards which haven't been solved yet.
+    fn unsolved(&self) -> impl Iterator<Item=&(Knowledge, BTreeSet<String>)> {
+        self.boards.iter().zip(&self.solved).filter(|(_, &solved)| !solved).map(|(board, _)| board)
+    }
+
+    /// Pick the guess from the dictionary which is worth the most over all the unsolved boards:
+    /// the information it's expected to give on each (the entropy of the feedback, in bits), plus
+    /// the chance of it being the answer on each. Solved boards don't count. Ties go to whichever
+    /// comes first in the dictionary.
+    ///
+    /// With lots of candidates left, only the best few guesses by letter frequency for each board
+    /// (see [`best_candidates`]) are scored. Returns None if there are no unsolved boards with
+    /// candidates left.
+    pub fn best_guess(&self, dictionary: &BTreeSet<String>, letter_freq: &HashMap<char, f64>)
+        -> Option<String>
+    {
+        let boards = self.unsolved()
+            .filter(|(_, candidates)| !candidates.is_empty())
+            .map(|(knowledge, candidates)| (knowledge, candidates.iter().collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        if boards.is_empty() {
+            return None;
+        }
+        let total = boards.iter().map(|(_, candidates)| candidates.len()).sum::<usize>();
+        let guesses = if total > MULTI_BOARD_SHORTLIST_ABOVE {
+            boards.iter()
+                .flat_map(|(knowledge, candidates)| {
+                    best_candidates(candidates.iter().copied(), knowledge, letter_freq)
+                })
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>()
+        } else {
+            dictionary.iter().collect()
+        };
+        let scored = guesses.par_iter()
+            .map(|&guess| {
+                let score = boards.iter()
+                    .map(|(_, candidates)| {
+                        let entropy = SplitMetric::Entropy
+                            .score_sizes(split_sizes(guess, candidates));
+                        let p_answer = if candidates.contains(&guess) {
+                            1. / candidates.len() as f64
+                        } else {
+                            0.
+                        };
+                        entropy + p_answer
+                    })
+                    .sum::<f64>();
+                (guess, NonNan::try_from(score).unwrap())
+            })
+            .collect::<Vec<_>>();
+        scored.into_iter()
+            .rev()
+            .max_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(guess, _)| guess.clone())
+    }
+}
@@ -0,0 +1,100 @@
+//! Writing the results of solving words, in a form meant for people or for other tools.
+
+use crate::*;
+use serde::Serialize;
+use std::io::Write;
+
+/// How results are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A line for each word, like `3 robot (2315) arose (57) motor (2) robot (1)`.
+    Text,
+
+    /// A JSON array with an object for each word.
+    Json,
+
+    /// A header line, then a row for each word, with the guesses made in the last column.
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!("unknown output format {:?}", other)),
+        }
+    }
+}
+
+/// How the solver did on one word, as written in JSON.
+#[derive(Debug, Serialize)]
+struct WordReport<'a> {
+    word: &'a str,
+    num_guesses: usize,
+    dict_size: usize,
+    guesses: Vec<GuessReport<'a>>,
+    solved: bool,
+    failed: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct GuessReport<'a> {
+    guess: &'a str,
+    remaining: usize,
+}
+
+impl<'a> From<&'a WordResult> for WordReport<'a> {
+    /// An empty guess at the end means the word wasn't found; it isn't listed, but still counts
+    /// towards the number of guesses, like in the text output.
+    fn from(result: &'a WordResult) -> Self {
+        Self {
+            word: &result.word,
+            num_guesses: result.num_guesses,
+            dict_size: result.dict_size,
+            guesses: result.guesses.iter()
+                .filter(|(guess, _)| !guess.is_empty())
+                .map(|(guess, remaining)| GuessReport { guess, remaining: *remaining })
+                .collect(),
+            solved: result.solved(),
+            failed: !result.solved(),
+        }
+    }
+}
+
+/// Write out the results in the given format.
+pub fn write_results(results: &[WordResult], format: OutputFormat, writer: &mut dyn Write)
+    -> io::Result<()>
+{
+    match format {
+        OutputFormat::Text => {
+            for result in results {
+                write!(writer, "{} {} ({})", result.num_guesses, result.word, result.dict_size)?;
+                for (guess, remaining) in &result.guesses {
+                    write!(writer, " {} ({})", guess, remaining)?;
+                }
+                writeln!(writer)?;
+            }
+        }
+        OutputFormat::Json => {
+            let reports = results.iter().map(WordReport::from).collect::<Vec<_>>();
+            serde_json::to_writer(&mut *writer, &reports)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Csv => {
+            writeln!(writer, "word,num_guesses,dict_size,solved,guesses")?;
+            for result in results {
+                let guesses = result.guesses.iter()
+                    .filter(|(guess, _)| !guess.is_empty())
+                    .map(|(guess, remaining)| format!("{}:{}", guess, remaining))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(writer, "{},{},{},{},{}", result.word, result.num_guesses,
+                    result.dict_size, result.solved(), guesses)?;
+            }
+        }
+    }
+    Ok(())
+}
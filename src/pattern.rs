@@ -0,0 +1,163 @@
+//! Feedback patterns boiled down to numbers, and a table of them for every guess and answer, so
+//! that scoring lots of guesses against the same answers doesn't keep calling [`check_guess`].
+
+use crate::*;
+
+/// The most letters a row of feedback can have for [`encode_pattern`]: 3^10 is the most ternary
+/// digits that fit in a `u16`.
+pub const MAX_PATTERN_LETTERS: usize = 10;
+
+/// Encode the colors of a row of feedback as a number: one ternary digit per letter, with the
+/// first letter most significant, green as 2, yellow as 1 and gray as 0.
+///
+/// # Panics
+///
+/// If the row has more than [`MAX_PATTERN_LETTERS`] letters.
+pub fn encode_pattern(infos: &[Info]) -> u16 {
+    assert!(infos.len() <= MAX_PATTERN_LETTERS,
+        "can't encode feedback for {} letters; the most is {}", infos.len(), MAX_PATTERN_LETTERS);
+    infos.iter().fold(0, |code, info| code * 3 + u16::from(color_digit(info)))
+}
+
+/// Like [`encode_pattern`], but for rows of up to 80 letters.
+pub(crate) fn encode_long_pattern(infos: &[Info]) -> u128 {
+    infos.iter().fold(0, |code, info| code * 3 + u128::from(color_digit(info)))
+}
+
+fn color_digit(info: &Info) -> u8 {
+    match info {
+        Info::Exact(_) => 2,
+        Info::Somewhere(_) => 1,
+        Info::No(_) => 0,
+    }
+}
+
+/// The feedback for the guess that was encoded by [`encode_pattern`].
+pub fn decode_pattern(code: u16, guess: &str) -> Vec<Info> {
+    let colors = guess.chars().rev()
+        .scan(code, |code, c| {
+            let info = match *code % 3 {
+                2 => Info::Exact(c),
+                1 => Info::Somewhere(c),
+                _ => Info::No(c),
+            };
+            *code /= 3;
+            Some(info)
+        })
+        .collect::<Vec<_>>();
+    colors.into_iter().rev().collect()
+}
+
+/// The encoded feedback for every pair of guess and answer from two fixed lists of words.
+#[derive(Debug, Clone)]
+pub struct PatternTable {
+    guesses: Vec<String>,
+    answers: Vec<String>,
+    /// Where each word is in `guesses` and `answers`.
+    guess_indices: HashMap<String, usize>,
+    answer_indices: HashMap<String, usize>,
+    /// Indexed by guess, then answer.
+    patterns: Vec<u16>,
+}
+
+impl PatternTable {
+    /// Work out the feedback for every pair, spread over all the CPUs. That's a lot of pairs for
+    /// big lists: the usual 12972 guesses and 2315 answers take 60 MB. Fails if any of the words
+    /// are longer than [`MAX_PATTERN_LETTERS`].
+    pub fn new<G, W>(guesses: &[G], answers: &[W]) -> Result<Self, SolveError>
+        where G: AsRef<str> + Sync,
+              W: AsRef<str> + Sync,
+    {
+        let longest = guesses.iter().map(|w| w.as_ref())
+            .chain(answers.iter().map(|w| w.as_ref()))
+            .map(|word| word.chars().count())
+            .max()
+            .unwrap_or(0);
+        if longest > MAX_PATTERN_LETTERS {
+            return Err(SolveError::PatternTooLong { num_letters: longest });
+        }
+        let patterns = guesses.par_iter()
+            .flat_map_iter(|guess| {
+                answers.iter().map(move |answer| {
+                    encode_pattern(&check_guess(answer.as_ref(), guess.as_ref()))
+                })
+            })
+            .collect();
+        let indices = |words: &[String]| {
+            words.iter().enumerate().map(|(idx, word)| (word.clone(), idx)).collect()
+        };
+        let guesses = guesses.iter().map(|w| w.as_ref().to_owned()).collect::<Vec<_>>();
+        let answers = answers.iter().map(|w| w.as_ref().to_owned()).collect::<Vec<_>>();
+        Ok(Self {
+            guess_indices: indices(&guesses),
+            answer_indices: indices(&answers),
+            guesses,
+            answers,
+            patterns,
+        })
+    }
+
+    pub fn guesses(&self) -> &[String] {
+        &self.guesses
+    }
+
+    pub fn answers(&self) -> &[String] {
+        &self.answers
+    }
+
+    /// The encoded feedback for the guess when the answer is the given one.
+    pub fn get(&self, guess_idx: usize, answer_idx: usize) -> u16 {
+        self.patterns[guess_idx * self.answers.len() + answer_idx]
+    }
+
+    /// The sizes of the groups the answers with the given indices are split into by the feedback
+    /// for the guess.
+    pub fn split_sizes(&self, guess_idx: usize, answer_indices: &[usize]) -> Vec<usize> {
+        group_sizes(answer_indices.iter().map(|&answer_idx| self.get(guess_idx, answer_idx)))
+    }
+
+    /// Rank every guess by how it splits up the answers with the given indices, like
+    /// [`rank_by_split`]: best first, with ties going to guesses which could be the answer.
+    /// This is what [`best_candidates_by_entropy`] does with [`SplitMetric::Entropy`], and
+    /// [`SplitMetric::WorstCase`] gives the same worst cases as [`best_candidates_minimax`].
+    pub fn rank(&self, answer_indices: &[usize], metric: SplitMetric) -> Vec<(String, f64)> {
+        self.rank_indices(0 .. self.guesses.len(), answer_indices, metric)
+    }
+
+    /// Rank the guesses by how they split up the candidates, exactly as [`rank_by_split`] does,
+    /// but looking up the feedback rather than working it out. Returns None if the table doesn't
+    /// have all the words.
+    pub fn rank_words(&self, guesses: &[&str], candidates: &[&str], metric: SplitMetric)
+        -> Option<Vec<(String, f64)>>
+    {
+        let guess_indices = guesses.iter()
+            .map(|&word| self.guess_indices.get(word).copied())
+            .collect::<Option<Vec<_>>>()?;
+        let answer_indices = candidates.iter()
+            .map(|&word| self.answer_indices.get(word).copied())
+            .collect::<Option<Vec<_>>>()?;
+        Some(self.rank_indices(guess_indices.into_iter(), &answer_indices, metric))
+    }
+
+    fn rank_indices(
+        &self,
+        guess_indices: impl Iterator<Item=usize>,
+        answer_indices: &[usize],
+        metric: SplitMetric,
+    ) -> Vec<(String, f64)> {
+        let remaining = answer_indices.iter()
+            .map(|&idx| self.answers[idx].as_str())
+            .collect::<BTreeSet<_>>();
+        let mut scored = guess_indices
+            .map(|guess_idx| {
+                let score = metric.score_sizes(self.split_sizes(guess_idx, answer_indices));
+                let guess = &self.guesses[guess_idx];
+                (guess.clone(), score, remaining.contains(guess.as_str()))
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by_cached_key(|(_, score, is_candidate)| {
+            (std::cmp::Reverse(NonNan::try_from(*score).unwrap()), !is_candidate)
+        });
+        scored.into_iter().map(|(word, score, _)| (word, score)).collect()
+    }
+}
@@ -0,0 +1,94 @@
+use crate::*;
+
+/// A whole game's worth of solver state: the words which could still be the answer, what's known
+/// about the answer so far, how to rank guesses, and the guesses made. This is the simplest way to
+/// drive the solver from another program.
+pub struct Solver {
+    candidates: Vec<String>,
+    knowledge: Knowledge,
+    strategy: Box<dyn GuessStrategy>,
+    history: Vec<(String, Vec<Info>)>,
+    hard_mode: bool,
+}
+
+impl Solver {
+    /// Start a game with the given dictionary. The word length is taken from the first word, and
+    /// any words of other lengths (or which aren't valid words) are dropped.
+    pub fn new(words: Vec<String>, strategy: Box<dyn GuessStrategy>) -> Self {
+        let num_letters = words.first().map_or(0, |word| word.chars().count());
+        let knowledge = Knowledge::new(num_letters);
+        let mut candidates = words;
+        candidates.retain(|word| knowledge.check_word(word, false));
+        candidates.sort_unstable();
+        candidates.dedup();
+        Self { candidates, knowledge, strategy, history: vec![], hard_mode: false }
+    }
+
+    /// Play in hard mode, where every guess has to fit what's known so far: see
+    /// [`Solver::apply_result`].
+    pub fn with_hard_mode(mut self, hard_mode: bool) -> Self {
+        self.hard_mode = hard_mode;
+        self
+    }
+
+    /// The best guesses to make next, best first. These are always candidates, so they're fine to
+    /// play in hard mode too.
+    pub fn suggest(&self) -> Vec<String> {
+        self.strategy.rank(&self.candidates, &self.knowledge)
+            .into_iter()
+            .take(10)
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    /// Take into account the feedback the game gave for a guess. Fails if the feedback is for the
+    /// wrong number of letters or contradicts what's already known, or in hard mode, if the guess
+    /// itself couldn't be the answer. Nothing is changed if it fails.
+    pub fn apply_result(&mut self, guess: &str, result: &[Info]) -> Result<(), SolveError> {
+        let expected = self.knowledge.num_letters();
+        if result.len() != expected {
+            return Err(SolveError::LengthMismatch { expected, actual: result.len() });
+        }
+        if self.hard_mode {
+            if let Err(violated_constraint) = self.knowledge.check_word_explain(guess) {
+                return Err(SolveError::HardModeViolation {
+                    guess: guess.to_owned(),
+                    violated_constraint,
+                });
+            }
+        }
+        let mut knowledge = self.knowledge.clone();
+        knowledge.add_infos(result, false)?;
+        self.knowledge = knowledge;
+        let knowledge = &self.knowledge;
+        self.candidates.retain(|word| knowledge.check_word(word, false));
+        self.history.push((guess.to_owned(), result.to_vec()));
+        Ok(())
+    }
+
+    /// The words which could still be the answer, in alphabetical order.
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    /// What's known about the answer so far.
+    pub fn knowledge(&self) -> &Knowledge {
+        &self.knowledge
+    }
+
+    /// The guesses made so far, with the feedback for each.
+    pub fn history(&self) -> &[(String, Vec<Info>)] {
+        &self.history
+    }
+
+    /// Whether the last guess was all green.
+    pub fn is_solved(&self) -> bool {
+        self.history.last()
+            .is_some_and(|(_, infos)| infos.iter().all(|info| matches!(info, Info::Exact(_))))
+    }
+
+    /// How many guesses have been made.
+    pub fn num_guesses(&self) -> usize {
+        self.history.len()
+    }
+}
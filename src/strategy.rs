@@ -0,0 +1,593 @@
+use crate::*;
+
+/// Which method to use for ranking guesses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Most unique letters, then most common letters (see [`best_candidates`]). Fast, but greedy.
+    Frequency,
+
+    /// Most information gained, on average. Quadratic in the number of candidates.
+    Entropy,
+
+    /// Fewest candidates expected to remain after this guess and the best follow-up guess. Cubic
+    /// in the number of candidates, so only suitable for the endgame.
+    Lookahead,
+
+    /// Most greens expected. Good for telling apart candidates which have (nearly) the same
+    /// letters in different places, but nothing else; see [`is_positional_endgame`].
+    Positional,
+
+    /// The frequency strategy's best few, then fewest candidates sharing the most common feedback
+    /// for them (see [`rank_by_discrimination`]). Between frequency and entropy for both cost and
+    /// quality.
+    Discriminating,
+
+    /// Pick one of the others depending on how many candidates are left: frequency when there are
+    /// lots, entropy when there are fewer than `entropy_below`, and lookahead when there are fewer
+    /// than `lookahead_below`. Positional once they're all (nearly) anagrams of each other.
+    Adaptive {
+        entropy_below: usize,
+        lookahead_below: usize,
+    },
+}
+
+impl Strategy {
+    /// The adaptive strategy, with its default thresholds.
+    pub const ADAPTIVE: Strategy = Strategy::Adaptive {
+        entropy_below: 1000,
+        lookahead_below: 50,
+    };
+
+    /// The strategy that will actually be used for ranking the given number of candidates.
+    pub fn for_pool_size(&self, num_candidates: usize) -> Strategy {
+        match *self {
+            Strategy::Adaptive { entropy_below, lookahead_below } => {
+                if num_candidates < lookahead_below {
+                    Strategy::Lookahead
+                } else if num_candidates < entropy_below {
+                    Strategy::Entropy
+                } else {
+                    Strategy::Frequency
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// The strategy that will actually be used for ranking the given candidates: like
+    /// [`for_pool_size`](Self::for_pool_size), except the adaptive strategy goes positional in an
+    /// anagram endgame (see [`is_positional_endgame`]).
+    pub fn for_candidates<W: AsRef<str>>(&self, candidates: &[W]) -> Strategy {
+        match self {
+            Strategy::Adaptive { .. } if is_positional_endgame(candidates) => Strategy::Positional,
+            _ => self.for_pool_size(candidates.len()),
+        }
+    }
+
+    /// The name of the strategy, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Frequency => "frequency",
+            Strategy::Entropy => "entropy",
+            Strategy::Lookahead => "lookahead",
+            Strategy::Positional => "positional",
+            Strategy::Discriminating => "discriminating",
+            Strategy::Adaptive { .. } => "adaptive",
+        }
+    }
+
+    /// A description of how guesses are being ranked, for showing to the user.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Strategy::Frequency => "By most unique letters and letter frequency",
+            Strategy::Entropy => "By most information gained",
+            Strategy::Lookahead => "By fewest candidates left after two guesses",
+            Strategy::Positional => "By most greens expected",
+            Strategy::Discriminating => "By letter frequency, then fewest left in the worst case",
+            Strategy::Adaptive { .. } => "By strategy depending on number of candidates",
+        }
+    }
+
+    /// Rank the candidates as guesses, best first.
+    pub fn rank<W: AsRef<str>>(
+        &self,
+        candidates: &[W],
+        knowledge: &Knowledge,
+        letter_freq: &HashMap<char, f64>,
+    ) -> Vec<String> {
+        self.rank_probes(candidates, candidates, knowledge, letter_freq)
+    }
+
+    /// Rank arbitrary probe words as guesses against the candidates, best first. The probes don't
+    /// have to be candidates (or even in the dictionary).
+    pub fn rank_probes<G, W>(
+        &self,
+        probes: &[G],
+        candidates: &[W],
+        knowledge: &Knowledge,
+        letter_freq: &HashMap<char, f64>,
+    ) -> Vec<String>
+        where G: AsRef<str>,
+              W: AsRef<str>,
+    {
+        self.rank_probes_with_table(probes, candidates, knowledge, letter_freq, None)
+    }
+
+    fn rank_probes_with_table<G, W>(
+        &self,
+        probes: &[G],
+        candidates: &[W],
+        knowledge: &Knowledge,
+        letter_freq: &HashMap<char, f64>,
+        table: Option<&PatternTable>,
+    ) -> Vec<String>
+        where G: AsRef<str>,
+              W: AsRef<str>,
+    {
+        let probes = probes.iter().map(|w| w.as_ref()).collect::<Vec<_>>();
+        let candidates = candidates.iter().map(|w| w.as_ref()).collect::<Vec<_>>();
+        self.guess_strategy_with_table(&candidates, letter_freq, table)
+            .rank_probes(&probes, &candidates, knowledge)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    /// The [`GuessStrategy`] which does this strategy's ranking for the given candidates (which
+    /// only matters for the adaptive strategy).
+    pub fn guess_strategy<W: AsRef<str>>(&self, candidates: &[W], letter_freq: &HashMap<char, f64>)
+        -> Box<dyn GuessStrategy>
+    {
+        self.guess_strategy_with_table(candidates, letter_freq, None)
+    }
+
+    fn guess_strategy_with_table<'a, W: AsRef<str>>(
+        &self,
+        candidates: &[W],
+        letter_freq: &HashMap<char, f64>,
+        table: Option<&'a PatternTable>,
+    ) -> Box<dyn GuessStrategy + 'a> {
+        match self.for_candidates(candidates) {
+            Strategy::Frequency => Box::new(FrequencyStrategy::new(letter_freq.clone())),
+            Strategy::Entropy => match table {
+                Some(table) => Box::new(TableStrategy { table, metric: SplitMetric::Entropy }),
+                None => Box::new(EntropyStrategy),
+            },
+            Strategy::Lookahead => Box::new(LookaheadStrategy),
+            Strategy::Positional => Box::new(PositionalStrategy),
+            Strategy::Discriminating => Box::new(DiscriminatingStrategy {
+                letter_freq: letter_freq.clone(),
+            }),
+            Strategy::Adaptive { .. } => unreachable!("for_candidates never returns Adaptive"),
+        }
+    }
+
+    /// Rank guesses from a separate pool (like a longer list of words the game accepts) against
+    /// the candidates. With only one or two candidates left, nothing beats guessing one of them, so
+    /// then only the candidates are ranked.
+    pub fn rank_guesses<G, W>(
+        &self,
+        guesses: &[G],
+        candidates: &[W],
+        knowledge: &Knowledge,
+        letter_freq: &HashMap<char, f64>,
+    ) -> Vec<String>
+        where G: AsRef<str>,
+              W: AsRef<str>,
+    {
+        self.rank_guesses_with_table(guesses, candidates, knowledge, letter_freq, None)
+    }
+
+    /// Like [`rank_guesses`](Self::rank_guesses), but ranking by information gained looks up the
+    /// feedback in the table (for words it has) rather than working it out. That's worth it when
+    /// ranking the same words over and over, like when solving lots of words.
+    pub fn rank_guesses_with_table<G, W>(
+        &self,
+        guesses: &[G],
+        candidates: &[W],
+        knowledge: &Knowledge,
+        letter_freq: &HashMap<char, f64>,
+        table: Option<&PatternTable>,
+    ) -> Vec<String>
+        where G: AsRef<str>,
+              W: AsRef<str>,
+    {
+        if candidates.len() <= 2 {
+            self.rank_probes_with_table(candidates, candidates, knowledge, letter_freq, table)
+        } else {
+            self.rank_probes_with_table(guesses, candidates, knowledge, letter_freq, table)
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "frequency" | "freq" => Ok(Strategy::Frequency),
+            "entropy" => Ok(Strategy::Entropy),
+            "lookahead" => Ok(Strategy::Lookahead),
+            "positional" => Ok(Strategy::Positional),
+            "discriminating" => Ok(Strategy::Discriminating),
+            "adaptive" => Ok(Strategy::ADAPTIVE),
+            other => Err(format!("unknown strategy {:?}", other)),
+        }
+    }
+}
+
+/// A pluggable method of ranking guesses. Each [`Strategy`] does its ranking with one of these;
+/// see [`Strategy::guess_strategy`].
+pub trait GuessStrategy: Send + Sync {
+    /// Rank probe words as guesses against the candidates, returning them with their scores,
+    /// highest (best) first. The probes don't have to be candidates.
+    fn rank_probes(&self, probes: &[&str], candidates: &[&str], knowledge: &Knowledge)
+        -> Vec<(String, f64)>;
+
+    /// Rank the candidates as guesses, returning them with their scores, highest (best) first.
+    fn rank(&self, candidates: &[String], knowledge: &Knowledge) -> Vec<(String, f64)> {
+        let candidates = candidates.iter().map(String::as_str).collect::<Vec<_>>();
+        self.rank_probes(&candidates, &candidates, knowledge)
+    }
+}
+
+/// Ranks by most unique letters, then most common letters not known about yet; see
+/// [`best_candidates`]. The score is the number of unique letters plus the frequencies of the
+/// letters not known about yet. Like `best_candidates`, this only returns the top 10 or so.
+#[derive(Debug, Clone, Default)]
+pub struct FrequencyStrategy {
+    pub letter_freq: HashMap<char, f64>,
+}
+
+impl FrequencyStrategy {
+    pub fn new(letter_freq: HashMap<char, f64>) -> Self {
+        Self { letter_freq }
+    }
+
+    /// The guts of [`best_candidates_avoiding`], returning the words with their scores.
+    pub(crate) fn rank_words<I, W>(
+        candidates: I,
+        knowledge: &Knowledge,
+        letter_freq: &HashMap<char, f64>,
+        recent_letters: &[char],
+        penalty: f64,
+        count_repeats: bool,
+    ) -> Vec<(W, f64)>
+        where I: Iterator<Item=W>,
+              W: AsRef<str>,
+    {
+        // Letters we already have knowledge about.
+        let is_known = |c: char| {
+            knowledge.must_have.contains_key(&c)
+                || knowledge.restrictions.iter().any(|r| {
+                    match r {
+                        Restriction::Not(v) => v.contains(c),
+                        Restriction::Exact(x) => *x == c,
+                    }
+                })
+        };
+
+        let mut by_letters = candidates
+            .map(|word| {
+                let count = if count_repeats {
+                    word.as_ref().chars().filter(|&c| !is_known(c)).count()
+                } else {
+                    unique_letters(word.as_ref())
+                };
+                (word, count)
+            })
+            .collect::<Vec<_>>();
+        by_letters.sort_unstable_by(|(_, c1), (_, c2)| c2.cmp(c1));
+
+        let score = |word: &str| {
+            word.chars()
+                .map(|c| {
+                    if recent_letters.contains(&c)
+                        && matches!(knowledge.key_status(c), KeyStatus::Absent | KeyStatus::Unknown)
+                    {
+                        // Recently tried letters which didn't pan out count against it.
+                        return -penalty * letter_freq.get(&c).copied().unwrap_or(0.);
+                    }
+                    // Letters we already have knowledge about count for zero.
+                    if is_known(c) {
+                        0.
+                    } else {
+                        // Otherwise, add up the frequency of letters in the dictionary.
+                        letter_freq.get(&c).copied().unwrap_or(0.)
+                    }
+                })
+                .sum::<f64>()
+        };
+
+        let mut results = vec![];
+
+        // Start with the words with the most unique letters. If that gives less than 10 results,
+        // then continue ranking and adding words with fewer unique letters.
+        let mut words = by_letters.into_iter().peekable();
+        while results.len() < 10 {
+            let Some(&(_, most_letters_count)) = words.peek() else {
+                break;
+            };
+            let mut most_unique_letters = vec![];
+            while let Some((word, _)) = words.next_if(|(_, count)| *count == most_letters_count) {
+                most_unique_letters.push(word);
+            }
+            let base = most_letters_count as f64;
+            if letter_freq.is_empty() {
+                // Without any letter frequencies, just go alphabetically.
+                most_unique_letters.sort_unstable_by(|a, b| a.as_ref().cmp(b.as_ref()));
+                results.extend(most_unique_letters.into_iter().map(|word| (word, base)));
+            } else {
+                // Sort the words by score, according to letter frequency.
+                let mut scored = most_unique_letters.into_iter()
+                    .map(|word| {
+                        let score = score(word.as_ref());
+                        (word, score)
+                    })
+                    .collect::<Vec<_>>();
+                scored.sort_by_cached_key(|(_, score)| {
+                    std::cmp::Reverse(NonNan::try_from(*score).unwrap())
+                });
+                results.extend(scored.into_iter().map(|(word, score)| (word, base + score)));
+            }
+        }
+        results
+    }
+}
+
+impl GuessStrategy for FrequencyStrategy {
+    fn rank_probes(&self, probes: &[&str], _candidates: &[&str], knowledge: &Knowledge)
+        -> Vec<(String, f64)>
+    {
+        Self::rank_words(probes.iter(), knowledge, &self.letter_freq, &[], 0., false)
+            .into_iter()
+            .map(|(word, score)| ((*word).to_owned(), score))
+            .collect()
+    }
+}
+
+/// Ranks by most information gained, on average; see [`rank_by_split`]. The score is the entropy
+/// in bits. The candidates are assumed to already match the knowledge.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EntropyStrategy;
+
+impl GuessStrategy for EntropyStrategy {
+    fn rank_probes(&self, probes: &[&str], candidates: &[&str], _knowledge: &Knowledge)
+        -> Vec<(String, f64)>
+    {
+        rank_by_split(probes, candidates, SplitMetric::Entropy)
+    }
+}
+
+/// Ranks by fewest candidates left in the worst case; see [`rank_by_worst_case`]. The score is
+/// that worst case, negated so that higher is better. Guesses after one which tells all the
+/// candidates apart aren't scored, and get negative infinity. The candidates are assumed to
+/// already match the knowledge.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinimaxStrategy;
+
+impl GuessStrategy for MinimaxStrategy {
+    fn rank_probes(&self, probes: &[&str], candidates: &[&str], _knowledge: &Knowledge)
+        -> Vec<(String, f64)>
+    {
+        rank_by_worst_case(probes, candidates)
+            .into_iter()
+            .map(|(word, worst)| (word, worst.map_or(f64::NEG_INFINITY, |worst| -(worst as f64))))
+            .collect()
+    }
+}
+
+/// Ranks by fewest candidates expected to be left after two guesses; see [`rank_by_lookahead`].
+/// The score is that number, negated so that higher is better.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LookaheadStrategy;
+
+impl GuessStrategy for LookaheadStrategy {
+    fn rank_probes(&self, probes: &[&str], candidates: &[&str], _knowledge: &Knowledge)
+        -> Vec<(String, f64)>
+    {
+        rank_by_lookahead(probes, candidates)
+    }
+}
+
+/// Ranks by most greens expected; see [`rank_by_expected_greens`]. The score is the number of
+/// greens expected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PositionalStrategy;
+
+impl GuessStrategy for PositionalStrategy {
+    fn rank_probes(&self, probes: &[&str], candidates: &[&str], _knowledge: &Knowledge)
+        -> Vec<(String, f64)>
+    {
+        rank_by_expected_greens(probes, candidates)
+    }
+}
+
+/// Ranks the frequency strategy's best few by their worst case; see [`rank_by_discrimination`].
+/// The score is how many candidates share the most common feedback, negated so that higher is
+/// better.
+#[derive(Debug, Clone, Default)]
+pub struct DiscriminatingStrategy {
+    pub letter_freq: HashMap<char, f64>,
+}
+
+impl GuessStrategy for DiscriminatingStrategy {
+    fn rank_probes(&self, probes: &[&str], candidates: &[&str], knowledge: &Knowledge)
+        -> Vec<(String, f64)>
+    {
+        rank_by_discrimination(probes, candidates, knowledge, &self.letter_freq)
+            .into_iter()
+            .map(|(word, most_common)| (word, -(most_common as f64)))
+            .collect()
+    }
+}
+
+/// Ranks by how the guesses split up the candidates, like [`rank_by_split`], but looking up the
+/// feedback in a [`PatternTable`]. Words the table doesn't have are ranked the slow way. With
+/// [`SplitMetric::Entropy`] this ranks exactly like [`EntropyStrategy`]; with
+/// [`SplitMetric::WorstCase`] it goes by the worst case like [`MinimaxStrategy`], but breaks ties
+/// the way [`rank_by_split`] does.
+#[derive(Debug, Clone, Copy)]
+pub struct TableStrategy<'a> {
+    pub table: &'a PatternTable,
+    pub metric: SplitMetric,
+}
+
+impl GuessStrategy for TableStrategy<'_> {
+    fn rank_probes(&self, probes: &[&str], candidates: &[&str], _knowledge: &Knowledge)
+        -> Vec<(String, f64)>
+    {
+        self.table.rank_words(probes, candidates, self.metric)
+            .unwrap_or_else(|| rank_by_split(probes, candidates, self.metric))
+    }
+}
+
+/// Score every guess by how it splits up the candidates, and return them best first, with their
+/// scores. Ties go to guesses which could be the answer, then to whichever came first.
+pub fn rank_by_split<G, W>(guesses: &[G], candidates: &[W], metric: SplitMetric)
+    -> Vec<(String, f64)>
+    where G: AsRef<str>,
+          W: AsRef<str>,
+{
+    let mut scored = guesses.iter()
+        .map(|guess| {
+            let guess = guess.as_ref();
+            let score = metric.score_sizes(split_sizes(guess, candidates));
+            let is_candidate = candidates.iter().any(|w| w.as_ref() == guess);
+            (guess.to_owned(), score, is_candidate)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by_cached_key(|(_, score, is_candidate)| {
+        (std::cmp::Reverse(NonNan::try_from(*score).unwrap()), !is_candidate)
+    });
+    scored.into_iter().map(|(word, score, _)| (word, score)).collect()
+}
+
+/// Rank each guess by how many candidates are expected to remain after guessing it and then making
+/// the best possible second guess (out of the candidates left). Returns the negated expected count
+/// (so higher is better), best first.
+pub fn rank_by_lookahead<G, W>(guesses: &[G], candidates: &[W]) -> Vec<(String, f64)>
+    where G: AsRef<str>,
+          W: AsRef<str>,
+{
+    let candidates = candidates.iter().map(|w| w.as_ref()).collect::<Vec<_>>();
+    let mut scored = guesses.iter()
+        .map(|guess| {
+            let guess = guess.as_ref();
+            (guess.to_owned(), -expected_remaining_after_two(guess, &candidates))
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by_cached_key(|(_, score)| std::cmp::Reverse(NonNan::try_from(*score).unwrap()));
+    scored
+}
+
+/// Rank each guess by how many greens it's expected to get, on average over the candidates, best
+/// first, with the expected count. Ties go to guesses which could be the answer, then to whichever
+/// came first.
+pub fn rank_by_expected_greens<G, W>(guesses: &[G], candidates: &[W]) -> Vec<(String, f64)>
+    where G: AsRef<str>,
+          W: AsRef<str>,
+{
+    let mut scored = guesses.iter()
+        .map(|guess| {
+            let guess = guess.as_ref();
+            let greens = candidates.iter()
+                .map(|word| {
+                    check_guess(word.as_ref(), guess).iter()
+                        .filter(|info| matches!(info, Info::Exact(_)))
+                        .count()
+                })
+                .sum::<usize>();
+            let score = greens as f64 / candidates.len().max(1) as f64;
+            let is_candidate = candidates.iter().any(|w| w.as_ref() == guess);
+            (guess.to_owned(), score, is_candidate)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by_cached_key(|(_, score, is_candidate)| {
+        (std::cmp::Reverse(NonNan::try_from(*score).unwrap()), !is_candidate)
+    });
+    scored.into_iter().map(|(word, score, _)| (word, score)).collect()
+}
+
+/// Take the best 10 guesses by letter frequency (as from [`best_candidates`]), and rank them by
+/// how many candidates share the feedback that's most common for them, fewest first, with the
+/// count. A guess which would give lots of candidates the same feedback doesn't tell them apart
+/// well. This only looks at a few guesses, so it's much cheaper than ranking them all by entropy.
+/// Ties keep the frequency order.
+pub fn rank_by_discrimination<G, W>(
+    guesses: &[G],
+    candidates: &[W],
+    knowledge: &Knowledge,
+    letter_freq: &HashMap<char, f64>,
+) -> Vec<(String, usize)>
+    where G: AsRef<str>,
+          W: AsRef<str>,
+{
+    let mut scored = best_candidates(guesses.iter().map(|w| w.as_ref()), knowledge, letter_freq)
+        .into_iter()
+        .take(10)
+        .map(|guess| {
+            let most_common = split_sizes(guess, candidates).into_iter().max().unwrap_or(0);
+            (guess.to_owned(), most_common)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by_key(|&(_, most_common)| most_common);
+    scored
+}
+
+/// Whether the candidates are all (nearly) anagrams of each other: more than one of them, each
+/// sharing all but at most one letter with the first. Then the letters are mostly known, and
+/// what's left is working out where they go.
+pub fn is_positional_endgame<W: AsRef<str>>(candidates: &[W]) -> bool {
+    let [first, rest @ ..] = candidates else {
+        return false;
+    };
+    if rest.is_empty() {
+        return false;
+    }
+    let first = first.as_ref();
+    rest.iter().all(|word| {
+        let mut letters = first.chars().collect::<Vec<_>>();
+        let mut unmatched = 0;
+        for c in word.as_ref().chars() {
+            match letters.iter().position(|&x| x == c) {
+                Some(i) => { letters.swap_remove(i); }
+                None => unmatched += 1,
+            }
+        }
+        unmatched <= 1
+    })
+}
+
+/// Split the candidates up by the feedback the guess would get.
+fn buckets<'a>(guess: &str, candidates: &[&'a str]) -> Vec<Vec<&'a str>> {
+    let mut buckets = HashMap::<Vec<Info>, Vec<&str>>::new();
+    for &word in candidates {
+        buckets.entry(check_guess(word, guess)).or_default().push(word);
+    }
+    buckets.into_values().collect()
+}
+
+/// The expected number of candidates remaining after the guess, counting a correct guess as
+/// leaving none.
+fn expected_remaining(guess: &str, candidates: &[&str]) -> f64 {
+    let sum_of_squares = buckets(guess, candidates)
+        .iter()
+        .filter(|bucket| bucket.as_slice() != [guess])
+        .map(|bucket| bucket.len() * bucket.len())
+        .sum::<usize>();
+    sum_of_squares as f64 / candidates.len() as f64
+}
+
+fn expected_remaining_after_two(guess: &str, candidates: &[&str]) -> f64 {
+    let total = buckets(guess, candidates)
+        .iter()
+        .filter(|bucket| bucket.as_slice() != [guess])
+        .map(|bucket| {
+            let best_second = bucket.iter()
+                .map(|second| expected_remaining(second, bucket))
+                .fold(f64::INFINITY, f64::min);
+            bucket.len() as f64 * best_second
+        })
+        .sum::<f64>();
+    total / candidates.len() as f64
+}
@@ -0,0 +1,130 @@
+use crate::*;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// One guess in a decision tree, and where the solver goes next depending on the feedback.
+#[derive(Debug, Clone)]
+pub struct DecisionNode {
+    /// The word guessed at this point.
+    pub guess: String,
+
+    /// The words which could still be the answer when this guess is made.
+    pub candidates: Vec<String>,
+
+    /// For each feedback the guess can get (other than all green), the next step.
+    pub children: Vec<(Vec<Info>, DecisionNode)>,
+}
+
+/// Build the tree of guesses the solver would make using the given strategy, starting with the
+/// given opener, for every possible answer in `candidates`.
+pub fn build_decision_tree<W: AsRef<str>>(
+    opener: &str,
+    candidates: &[W],
+    knowledge: &Knowledge,
+    letter_freq: &HashMap<char, f64>,
+    strategy: &Strategy,
+) -> DecisionNode {
+    // Group the candidates by the feedback they'd give. BTreeMap keyed by the formatted feedback
+    // keeps the children in a deterministic order.
+    let mut buckets = BTreeMap::<String, (Vec<Info>, Vec<String>)>::new();
+    for word in candidates {
+        let infos = check_guess(word.as_ref(), opener);
+        buckets.entry(format_infos(&infos))
+            .or_insert_with(|| (infos, vec![]))
+            .1
+            .push(word.as_ref().to_owned());
+    }
+
+    let mut children = vec![];
+    for (infos, words) in buckets.into_values() {
+        if infos.iter().all(|info| matches!(info, Info::Exact(_))) {
+            continue;
+        }
+        let mut k2 = knowledge.clone();
+        if k2.add_infos(&infos, false).is_err() {
+            continue;
+        }
+        let next = strategy.rank(&words, &k2, letter_freq).swap_remove(0);
+        children.push((infos, build_decision_tree(&next, &words, &k2, letter_freq, strategy)));
+    }
+
+    DecisionNode {
+        guess: opener.to_owned(),
+        candidates: candidates.iter().map(|w| w.as_ref().to_owned()).collect(),
+        children,
+    }
+}
+
+impl DecisionNode {
+    /// How many guesses it takes to find each answer, starting with (and counting) this one.
+    pub fn guesses_per_answer(&self) -> Vec<(String, usize)> {
+        let mut result = vec![];
+        if self.candidates.contains(&self.guess) {
+            result.push((self.guess.clone(), 1));
+        }
+        for (_, child) in &self.children {
+            result.extend(child.guesses_per_answer()
+                .into_iter()
+                .map(|(answer, n)| (answer, n + 1)));
+        }
+        result
+    }
+
+    /// The average number of guesses it takes to find the answer, starting with (and counting)
+    /// this one, if every candidate is equally likely.
+    pub fn expected_guesses(&self) -> f64 {
+        let counts = self.guesses_per_answer();
+        counts.iter().map(|(_, n)| n).sum::<usize>() as f64 / counts.len().max(1) as f64
+    }
+}
+
+/// The most guesses the solver ever needs to find any word in the dictionary, starting with the
+/// given opener and then following the strategy (with letter frequencies from the dictionary).
+pub fn worst_case_for_opener<W: AsRef<str>>(opener: &str, dictionary: &[W], strategy: &Strategy)
+    -> usize
+{
+    let Some(first) = dictionary.first() else {
+        return 0;
+    };
+    let knowledge = Knowledge::new(first.as_ref().chars().count());
+    let letter_freq = letter_frequencies(dictionary.iter().map(|w| w.as_ref()));
+    build_decision_tree(opener, dictionary, &knowledge, &letter_freq, strategy)
+        .guesses_per_answer()
+        .into_iter()
+        .map(|(_, n)| n)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Write a decision tree as a Graphviz DOT graph. Nodes are guesses (with the words which could
+/// still be the answer at that point), and edges are labeled with the feedback.
+pub fn write_dot(tree: &DecisionNode, w: &mut impl Write) -> io::Result<()> {
+    writeln!(w, "digraph decision_tree {{")?;
+    writeln!(w, "    node [shape=box];")?;
+    let mut next_id = 0;
+    write_dot_node(tree, &mut next_id, w)?;
+    writeln!(w, "}}")
+}
+
+fn write_dot_node(node: &DecisionNode, next_id: &mut usize, w: &mut impl Write)
+    -> io::Result<usize>
+{
+    let id = *next_id;
+    *next_id += 1;
+
+    // Listing every candidate makes the top of the tree unreadable, so only list small sets.
+    let answers = if node.candidates.len() <= 5 {
+        node.candidates.join(" ")
+    } else {
+        format!("{} candidates", node.candidates.len())
+    };
+    let solved = node.candidates.contains(&node.guess);
+    writeln!(w, "    n{} [label=\"{}\\n{}\"{}];", id, node.guess, answers,
+        if solved { ", peripheries=2" } else { "" })?;
+
+    for (infos, child) in &node.children {
+        let child_id = write_dot_node(child, next_id, w)?;
+        writeln!(w, "    n{} -> n{} [label=\"{}\"];", id, child_id, format_infos(infos))?;
+    }
+    Ok(id)
+}